@@ -0,0 +1,259 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::{Days, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use surrealdb::RecordId;
+
+use crate::{
+    api::{
+        ApiError, ApiState,
+        auth::extractor::AuthUser,
+        defs::{TimezoneQuery, start_of_day},
+    },
+    billing,
+    db::{DbError, repo::AccountRepo},
+    models::{Account, AccountKind, BillingCycle},
+    recurrence::to_utc,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemPayload {
+    name: String,
+    kind: AccountKind,
+    #[serde(default)]
+    opening_balance: f64,
+    statement_day: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct CyclesQuery {
+    #[serde(default = "default_cycles")]
+    count: u32,
+}
+
+fn default_cycles() -> u32 {
+    6
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditedAccount {
+    name: String,
+    kind: AccountKind,
+    opening_balance: f64,
+    statement_day: Option<u32>,
+}
+
+const MAX_CYCLES: u32 = 24;
+
+fn validate_statement_day(payload: &ItemPayload) -> Result<(), ApiError> {
+    match payload.statement_day {
+        Some(_) if !matches!(payload.kind, AccountKind::Credit) => Err(ApiError::Validation(
+            json!({"statementDay": "Only credit accounts have a statement day"}),
+        )),
+        Some(day) if !(1..=31).contains(&day) => Err(ApiError::Validation(
+            json!({"statementDay": "Statement day must be between 1 and 31"}),
+        )),
+        _ => Ok(()),
+    }
+}
+
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = AccountRepo::new(&state.db);
+
+    let accounts = repo.list(auth.user_id).await?;
+
+    Ok(Json(accounts))
+}
+
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = AccountRepo::new(&state.db);
+
+    validate_statement_day(&payload)?;
+
+    let user_id = auth.user_id;
+    let name = payload.name;
+
+    if repo.exists(user_id.clone(), name.clone()).await? {
+        return Err(ApiError::AlreadyExists(json!(
+            {"name": "Account with this name already exists"}
+        )));
+    }
+
+    let account_id = repo
+        .create(
+            user_id,
+            name.clone(),
+            payload.kind,
+            payload.opening_balance,
+            payload.statement_day,
+        )
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Account {
+            id: account_id,
+            name,
+            kind: payload.kind,
+            opening_balance: payload.opening_balance,
+            statement_day: payload.statement_day,
+            balance: payload.opening_balance,
+        }),
+    ))
+}
+
+pub async fn edit(
+    State(state): State<Arc<ApiState>>,
+    Path(account_id): Path<String>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = AccountRepo::new(&state.db);
+
+    validate_statement_day(&payload)?;
+
+    let user_id = auth.user_id;
+    let account_id = RecordId::from_table_key("account", account_id);
+    let name = payload.name;
+
+    if !(repo.user_owns(user_id.clone(), account_id.clone()).await?) {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this account".into(),
+        )));
+    }
+
+    if repo
+        .exists_excluding(user_id, name.clone(), account_id.clone())
+        .await?
+    {
+        return Err(ApiError::AlreadyExists(json!(
+            {"name": "Account with this name already exists"}
+        )));
+    }
+
+    repo.edit(
+        account_id,
+        name.clone(),
+        payload.kind,
+        payload.opening_balance,
+        payload.statement_day,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(EditedAccount {
+            name,
+            kind: payload.kind,
+            opening_balance: payload.opening_balance,
+            statement_day: payload.statement_day,
+        }),
+    ))
+}
+
+pub async fn delete(
+    State(state): State<Arc<ApiState>>,
+    Path(account_id): Path<String>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = AccountRepo::new(&state.db);
+
+    let account_id = RecordId::from_table_key("account", account_id);
+
+    if !(repo.user_owns(auth.user_id, account_id.clone()).await?) {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this account".into(),
+        )));
+    }
+
+    repo.delete(account_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Groups the spending on a credit account by billing cycle, newest first.
+/// Cycles run from midnight to midnight in the user's timezone.
+pub async fn cycles(
+    State(state): State<Arc<ApiState>>,
+    Path(account_id): Path<String>,
+    Query(query): Query<CyclesQuery>,
+    Query(tz): Query<TimezoneQuery>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = AccountRepo::new(&state.db);
+
+    let account_id = RecordId::from_table_key("account", account_id);
+
+    if !(repo
+        .user_owns(auth.user_id.clone(), account_id.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this account".into(),
+        )));
+    }
+
+    let Some(statement_day) = repo.statement_day(account_id.clone()).await? else {
+        return Err(ApiError::Validation(json!(
+            {"statementDay": "Account has no statement day"}
+        )));
+    };
+
+    let tz = tz.resolve(&state, auth.user_id).await?;
+
+    let cycles = billing::cycles(
+        statement_day,
+        Utc::now().with_timezone(&tz).date_naive(),
+        query.count.clamp(1, MAX_CYCLES),
+    );
+
+    let (Some((_, closes)), Some((opens, _))) = (cycles.first(), cycles.last()) else {
+        return Ok(Json(Vec::new()));
+    };
+
+    let entries = repo
+        .entries(
+            account_id,
+            start_of_day(*opens, tz).into(),
+            start_of_day(*closes + Days::new(1), tz).into(),
+        )
+        .await?;
+
+    let cycles = cycles
+        .into_iter()
+        .map(|(opens, closes)| {
+            let amounts = entries
+                .iter()
+                .filter(|entry| {
+                    (opens..=closes).contains(&to_utc(&entry.date).with_timezone(&tz).date_naive())
+                })
+                .map(|entry| entry.amount)
+                .collect::<Vec<_>>();
+
+            BillingCycle {
+                opens,
+                closes,
+                amount: amounts.iter().sum(),
+                transactions: amounts.len(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Json(cycles))
+}
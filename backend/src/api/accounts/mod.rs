@@ -0,0 +1,48 @@
+mod handlers;
+mod reconciliations;
+mod transfers;
+
+use std::sync::Arc;
+
+use axum::{
+    Router, middleware,
+    routing::{delete, get, patch, post},
+};
+
+use crate::api::{ApiState, auth::middleware::require_auth};
+
+pub fn router(state: Arc<ApiState>) -> Router<Arc<ApiState>> {
+    let account_router = Router::new()
+        .route("/edit", patch(handlers::edit))
+        .route("/delete", delete(handlers::delete))
+        .route("/cycles", get(handlers::cycles));
+
+    let reconciliations_router = Router::new().route("/create", post(reconciliations::create));
+    let reconciliation_router = Router::new()
+        .route("/", get(reconciliations::get))
+        .route("/mark", post(reconciliations::mark))
+        .route("/finish", post(reconciliations::finish))
+        .route("/delete", delete(reconciliations::delete));
+
+    let transfers_router = Router::new()
+        .route("/create", post(transfers::create))
+        .route("/list", get(transfers::list));
+    let transfer_router = Router::new().route("/delete", delete(transfers::delete));
+
+    Router::new()
+        .route("/", get(handlers::list))
+        .route("/create", post(handlers::create))
+        .nest(
+            "/{id}",
+            account_router
+                .nest(
+                    "/reconciliations",
+                    reconciliations_router.nest("/{id}", reconciliation_router),
+                )
+                .nest(
+                    "/transfers",
+                    transfers_router.nest("/{id}", transfer_router),
+                ),
+        )
+        .layer(middleware::from_fn_with_state(state, require_auth))
+}
@@ -0,0 +1,223 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+use surrealdb::{Datetime, RecordId};
+
+use crate::{
+    api::{ApiError, ApiState, auth::extractor::AuthUser},
+    db::{
+        DbError,
+        repo::{AccountRepo, ReconciliationRepo},
+    },
+    models::{ReconciliationReport, TransactionStatus},
+};
+
+/// How far the cleared balance may be off from the statement for the
+/// reconciliation to be finished, to allow for rounding.
+const BALANCE_TOLERANCE: f64 = 0.005;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemPayload {
+    statement_date: Datetime,
+    closing_balance: f64,
+}
+
+#[derive(Deserialize)]
+pub struct MarkPayload {
+    transactions: Vec<String>,
+    cleared: bool,
+}
+
+async fn owned_reconciliation(
+    state: &ApiState,
+    user_id: RecordId,
+    account_id: String,
+    reconciliation_id: String,
+) -> Result<(RecordId, RecordId), ApiError> {
+    let account_id = RecordId::from_table_key("account", account_id);
+    let reconciliation_id = RecordId::from_table_key("reconciliation", reconciliation_id);
+
+    if !(ReconciliationRepo::new(&state.db)
+        .user_owns(user_id, account_id.clone(), reconciliation_id.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this reconciliation".into(),
+        )));
+    }
+
+    Ok((account_id, reconciliation_id))
+}
+
+async fn report(
+    repo: &ReconciliationRepo<'_>,
+    reconciliation_id: RecordId,
+) -> Result<ReconciliationReport, ApiError> {
+    Ok(ReconciliationReport {
+        reconciliation: repo.get(reconciliation_id.clone()).await?,
+        transactions: repo.items(reconciliation_id).await?,
+    })
+}
+
+fn finished() -> ApiError {
+    ApiError::Locked(json!(
+        {"reconciliation": "This reconciliation has already been finished"}
+    ))
+}
+
+/// Starts reconciling the account against a statement. An account has at most
+/// one reconciliation in progress.
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    Path(account_id): Path<String>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = ReconciliationRepo::new(&state.db);
+
+    let account_id = RecordId::from_table_key("account", account_id);
+
+    if !(AccountRepo::new(&state.db)
+        .user_owns(auth.user_id, account_id.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this account".into(),
+        )));
+    }
+
+    if repo.open(account_id.clone()).await?.is_some() {
+        return Err(ApiError::AlreadyExists(json!(
+            {"reconciliation": "This account already has a reconciliation in progress"}
+        )));
+    }
+
+    let reconciliation_id = repo
+        .create(account_id, payload.statement_date, payload.closing_balance)
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(report(&repo, reconciliation_id).await?),
+    ))
+}
+
+pub async fn get(
+    State(state): State<Arc<ApiState>>,
+    Path((account_id, reconciliation_id)): Path<(String, String)>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = ReconciliationRepo::new(&state.db);
+
+    let (_, reconciliation_id) =
+        owned_reconciliation(&state, auth.user_id, account_id, reconciliation_id).await?;
+
+    Ok(Json(report(&repo, reconciliation_id).await?))
+}
+
+/// Marks transactions as cleared, or back as pending, and returns the updated
+/// report. Transactions that are not on the account are ignored.
+pub async fn mark(
+    State(state): State<Arc<ApiState>>,
+    Path((account_id, reconciliation_id)): Path<(String, String)>,
+    auth: AuthUser,
+    Json(payload): Json<MarkPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = ReconciliationRepo::new(&state.db);
+
+    let (account_id, reconciliation_id) =
+        owned_reconciliation(&state, auth.user_id, account_id, reconciliation_id).await?;
+
+    if repo
+        .get(reconciliation_id.clone())
+        .await?
+        .finished_at
+        .is_some()
+    {
+        return Err(finished());
+    }
+
+    let status = if payload.cleared {
+        TransactionStatus::Cleared
+    } else {
+        TransactionStatus::Pending
+    };
+
+    repo.mark(
+        account_id,
+        payload
+            .transactions
+            .into_iter()
+            .map(|transaction| RecordId::from_table_key("transaction", transaction))
+            .collect(),
+        status,
+    )
+    .await?;
+
+    Ok(Json(report(&repo, reconciliation_id).await?))
+}
+
+/// Finishes the reconciliation once the cleared balance matches the
+/// statement, locking the cleared transactions against edits and deletion.
+pub async fn finish(
+    State(state): State<Arc<ApiState>>,
+    Path((account_id, reconciliation_id)): Path<(String, String)>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = ReconciliationRepo::new(&state.db);
+
+    let (_, reconciliation_id) =
+        owned_reconciliation(&state, auth.user_id, account_id, reconciliation_id).await?;
+
+    let reconciliation = repo.get(reconciliation_id.clone()).await?;
+
+    if reconciliation.finished_at.is_some() {
+        return Err(finished());
+    }
+
+    if reconciliation.difference.abs() > BALANCE_TOLERANCE {
+        return Err(ApiError::Validation(json!({
+            "difference": format!(
+                "Cleared balance is off from the statement by {:.2}",
+                reconciliation.difference
+            )
+        })));
+    }
+
+    repo.finish(reconciliation_id.clone()).await?;
+
+    Ok(Json(report(&repo, reconciliation_id).await?))
+}
+
+/// Abandons a reconciliation in progress. Cleared marks are kept.
+pub async fn delete(
+    State(state): State<Arc<ApiState>>,
+    Path((account_id, reconciliation_id)): Path<(String, String)>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = ReconciliationRepo::new(&state.db);
+
+    let (_, reconciliation_id) =
+        owned_reconciliation(&state, auth.user_id, account_id, reconciliation_id).await?;
+
+    if repo
+        .get(reconciliation_id.clone())
+        .await?
+        .finished_at
+        .is_some()
+    {
+        return Err(finished());
+    }
+
+    repo.delete(reconciliation_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+use surrealdb::{Datetime, RecordId};
+
+use crate::{
+    api::{
+        ApiError, ApiState,
+        auth::extractor::AuthUser,
+        defs::{DateRange, TimezoneQuery},
+    },
+    db::{DbError, repo::AccountRepo},
+    models::Transfer,
+};
+
+#[derive(Deserialize)]
+pub struct ItemPayload {
+    pub to: String,
+    pub amount: f64,
+    pub note: Option<String>,
+    pub date: Datetime,
+}
+
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    Path(account_id): Path<String>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = AccountRepo::new(&state.db);
+
+    let user_id = auth.user_id;
+    let from_account = RecordId::from_table_key("account", account_id);
+    let to_account = RecordId::from_table_key("account", payload.to);
+
+    if payload.amount <= 0.0 {
+        return Err(ApiError::Validation(json!(
+            {"amount": "Transfer amount must be greater than zero"}
+        )));
+    }
+
+    if from_account == to_account {
+        return Err(ApiError::Validation(json!(
+            {"to": "Cannot transfer to the same account"}
+        )));
+    }
+
+    for account_id in [&from_account, &to_account] {
+        if !(repo.user_owns(user_id.clone(), account_id.clone()).await?) {
+            return Err(ApiError::Db(DbError::NotFound(
+                "User does not own this account".into(),
+            )));
+        }
+    }
+
+    let transfer_id = repo
+        .create_transfer(
+            from_account.clone(),
+            to_account.clone(),
+            payload.amount,
+            payload.note.clone(),
+            payload.date.clone(),
+        )
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Transfer {
+            id: transfer_id,
+            from_account,
+            to_account,
+            amount: payload.amount,
+            note: payload.note,
+            date: payload.date,
+        }),
+    ))
+}
+
+pub async fn delete(
+    State(state): State<Arc<ApiState>>,
+    Path((_, transfer_id)): Path<(String, String)>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = AccountRepo::new(&state.db);
+
+    let transfer_id = RecordId::from_table_key("transfer", transfer_id);
+
+    if !(repo
+        .user_owns_transfer(auth.user_id, transfer_id.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this transfer".into(),
+        )));
+    }
+
+    repo.delete_transfer(transfer_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    Path(account_id): Path<String>,
+    Query(range): Query<DateRange>,
+    Query(tz): Query<TimezoneQuery>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let tz = tz.resolve(&state, auth.user_id.clone()).await?;
+    let (start, end) = range.resolve(tz)?;
+
+    let repo = AccountRepo::new(&state.db);
+
+    let account_id = RecordId::from_table_key("account", account_id);
+
+    if !(repo.user_owns(auth.user_id, account_id.clone()).await?) {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this account".into(),
+        )));
+    }
+
+    let transfers = repo.list_transfers(account_id, start, end).await?;
+
+    Ok(Json(transfers))
+}
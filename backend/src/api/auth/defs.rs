@@ -22,3 +22,9 @@ pub struct AuthResponse {
     pub token_type: &'static str,
     pub expires_at: Datetime,
 }
+
+#[derive(Deserialize)]
+pub struct SettingsPayload {
+    /// An IANA timezone name; `null` resets it to UTC.
+    pub timezone: Option<String>,
+}
@@ -16,11 +16,12 @@ use crate::{
     api::{
         ApiError, ApiState,
         auth::{
-            defs::{AuthResponse, SignInPayload, SignUpPayload},
+            defs::{AuthResponse, SettingsPayload, SignInPayload, SignUpPayload},
             extractor::AuthUser,
             jwt::Claims,
             util::{hash_password, verify_password},
         },
+        defs::parse_timezone,
     },
     config::config,
     db::repo::UserRepo,
@@ -142,3 +143,23 @@ pub async fn refresh(
 pub async fn me(_: AuthUser) -> impl IntoResponse {
     StatusCode::NO_CONTENT
 }
+
+/// Updates the user's settings. The timezone decides how plain dates and
+/// daily totals are read unless a request passes its own `tz`.
+pub async fn update_settings(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    Json(payload): Json<SettingsPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    // Store the canonical name so lookups never fail later.
+    let timezone = payload
+        .timezone
+        .map(|tz| parse_timezone(&tz).map(|tz| tz.name().to_string()))
+        .transpose()?;
+
+    UserRepo::new(&state.db)
+        .set_timezone(auth.user_id, timezone)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
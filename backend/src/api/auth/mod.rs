@@ -22,6 +22,7 @@ pub fn router(state: Arc<ApiState>) -> Router<Arc<ApiState>> {
         .route(
             "/me",
             get(handlers::me)
+                .patch(handlers::update_settings)
                 .route_layer(axum::middleware::from_fn_with_state(state, require_auth)),
         )
 }
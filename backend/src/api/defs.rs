@@ -1,9 +1,51 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use serde::Deserialize;
-use surrealdb::Datetime;
+use serde_json::json;
+use surrealdb::{Datetime, RecordId};
+
+use crate::{
+    api::{ApiError, ApiState},
+    db::repo::UserRepo,
+};
+
+/// One end of a `DateRange`: either an exact instant or a whole calendar day,
+/// which only becomes an instant once the user's timezone is known.
+enum Bound {
+    Instant(DateTime<Utc>),
+    Day(NaiveDate),
+}
+
+impl Bound {
+    fn parse(value: &str) -> Option<Self> {
+        if let Ok(instant) = DateTime::parse_from_rfc3339(value) {
+            return Some(Self::Instant(instant.to_utc()));
+        }
+
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .ok()
+            .map(Self::Day)
+    }
+}
 
+/// Returns the instant `date` starts at in `tz`. Days that skip midnight for
+/// daylight saving start at the first instant that does exist.
+pub fn start_of_day(date: NaiveDate, tz: Tz) -> DateTime<Utc> {
+    let midnight = date.and_time(NaiveTime::MIN);
+
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .unwrap_or_else(|| tz.from_utc_datetime(&midnight))
+        .to_utc()
+}
+
+/// A `start`/`end` query range. Each end is either an RFC 3339 datetime or a
+/// `YYYY-MM-DD` date covering that whole day in the user's timezone.
 pub struct DateRange {
-    pub start: Datetime,
-    pub end: Datetime,
+    start: Bound,
+    end: Bound,
 }
 
 impl<'de> Deserialize<'de> for DateRange {
@@ -13,21 +55,92 @@ impl<'de> Deserialize<'de> for DateRange {
     {
         #[derive(Deserialize)]
         struct Raw {
-            start: Datetime,
-            end: Datetime,
+            start: String,
+            end: String,
         }
 
         let raw = Raw::deserialize(deserializer)?;
 
-        if raw.start >= raw.end {
-            return Err(serde::de::Error::custom(
-                "start_date must be before end_date",
-            ));
-        }
+        let bound = |value: &str| {
+            Bound::parse(value).ok_or_else(|| {
+                serde::de::Error::custom(format!(
+                    "invalid date `{value}`, expected RFC 3339 or YYYY-MM-DD"
+                ))
+            })
+        };
 
         Ok(Self {
-            start: raw.start,
-            end: raw.end,
+            start: bound(&raw.start)?,
+            end: bound(&raw.end)?,
         })
     }
 }
+
+impl DateRange {
+    /// Resolves the range into inclusive instants, reading dates in `tz`.
+    pub fn resolve(self, tz: Tz) -> Result<(Datetime, Datetime), ApiError> {
+        let start = match self.start {
+            Bound::Instant(instant) => instant,
+            Bound::Day(date) => start_of_day(date, tz),
+        };
+
+        let end = match self.end {
+            Bound::Instant(instant) => Some(instant),
+            Bound::Day(date) => date
+                .checked_add_days(Days::new(1))
+                .map(|next| start_of_day(next, tz) - chrono::Duration::nanoseconds(1)),
+        };
+
+        match end {
+            Some(end) if start < end => Ok((start.into(), end.into())),
+            _ => Err(ApiError::Validation(json!(
+                {"start": "Start date must be before the end date"}
+            ))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TagFilter {
+    pub tag: Option<String>,
+}
+
+impl TagFilter {
+    pub fn tag_id(self) -> Option<RecordId> {
+        self.tag.map(|tag| RecordId::from_table_key("tag", tag))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingFilter {
+    #[serde(default)]
+    pub include_pending: bool,
+}
+
+/// An IANA timezone such as `Asia/Singapore`, overriding the user's own
+/// setting for a single request.
+#[derive(Deserialize)]
+pub struct TimezoneQuery {
+    pub tz: Option<String>,
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz, ApiError> {
+    Tz::from_str(name).map_err(|_| ApiError::Validation(json!({"tz": "Unknown timezone"})))
+}
+
+impl TimezoneQuery {
+    /// Picks the requested timezone, falling back to the user's setting and
+    /// then to UTC.
+    pub async fn resolve(self, state: &ApiState, user_id: RecordId) -> Result<Tz, ApiError> {
+        if let Some(tz) = self.tz {
+            return parse_timezone(&tz);
+        }
+
+        let tz = UserRepo::new(&state.db).timezone(user_id).await?;
+
+        // A stored name that no longer parses falls back to UTC rather than
+        // failing every request.
+        Ok(tz.and_then(|tz| Tz::from_str(&tz).ok()).unwrap_or(Tz::UTC))
+    }
+}
@@ -55,6 +55,12 @@ impl From<ApiError> for ApiErrorResponse {
                 detail: "A record with the provided details already exists.".into(),
                 error: Some(e),
             },
+            ApiError::Locked(e) => ApiErrorResponse {
+                title: "Locked",
+                status: StatusCode::CONFLICT,
+                detail: "The record is locked against changes".into(),
+                error: Some(e),
+            },
             ApiError::Db(e) => match e {
                 DbError::NotFound(e) => ApiErrorResponse {
                     title: "Record Not Found",
@@ -88,7 +94,9 @@ impl From<ApiError> for ApiErrorResponse {
                     },
                 }
             }
-            ApiError::Task(_) | ApiError::PasswordHash(_) => Self::internal_server_error(),
+            ApiError::Task(_) | ApiError::PasswordHash(_) | ApiError::Io(_) => {
+                Self::internal_server_error()
+            }
         }
     }
 }
@@ -115,6 +123,9 @@ pub enum ApiError {
     #[error("unauthorized")]
     Unauthorized(Value),
 
+    #[error("record is locked")]
+    Locked(Value),
+
     #[error("database error: {0}")]
     Db(#[from] DbError),
 
@@ -126,6 +137,9 @@ pub enum ApiError {
 
     #[error("password hashing error: {0}")]
     PasswordHash(#[from] password_hash::Error),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 impl IntoResponse for ApiError {
@@ -135,6 +149,7 @@ impl IntoResponse for ApiError {
         match &self {
             ApiError::Task(e) => error!("Task error: {e}"),
             ApiError::PasswordHash(e) => error!("Password hashing error: {e}"),
+            ApiError::Io(e) => error!("IO error: {e}"),
             ApiError::Db(e) => match e {
                 DbError::NotCreated(t) => error!("Record not created for table: {t}"),
                 DbError::NotFound(_) => (),
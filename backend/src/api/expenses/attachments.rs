@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Multipart, Path, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use serde_json::json;
+use surrealdb::RecordId;
+
+use crate::{
+    api::{
+        ApiError, ApiState, auth::extractor::AuthUser, expenses::transactions::owned_transaction,
+    },
+    config::config,
+    db::repo::AttachmentRepo,
+    storage,
+};
+
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    Path((_, transaction_id)): Path<(String, String)>,
+    auth: AuthUser,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = AttachmentRepo::new(&state.db);
+
+    let transaction_id = owned_transaction(&state, auth.user_id, transaction_id).await?;
+
+    let invalid_file = |e: axum::extract::multipart::MultipartError| {
+        ApiError::Validation(json!({ "file": e.body_text() }))
+    };
+
+    let field = loop {
+        match multipart.next_field().await.map_err(invalid_file)? {
+            Some(field) if field.name() == Some("file") => break field,
+            Some(_) => continue,
+            None => {
+                return Err(ApiError::Validation(json!(
+                    {"file": "A file is required"}
+                )));
+            }
+        }
+    };
+
+    let name = field.file_name().unwrap_or("receipt").to_string();
+    let bytes = field.bytes().await.map_err(invalid_file)?;
+
+    if bytes.len() > config().attachments.max_size {
+        return Err(ApiError::Validation(json!(
+            {"file": "File exceeds the maximum upload size"}
+        )));
+    }
+
+    let Some(mime) = storage::sniff_mime(&bytes) else {
+        return Err(ApiError::Validation(json!(
+            {"file": "Only JPEG, PNG, GIF, WebP, HEIC and PDF files are supported"}
+        )));
+    };
+
+    let hash = storage::digest(&bytes);
+
+    // The row goes in first so that the attachment purge never sees a file
+    // that is about to be referenced as unused.
+    let attachment = repo
+        .create(
+            transaction_id,
+            name,
+            mime.to_string(),
+            bytes.len() as u64,
+            hash.clone(),
+        )
+        .await?;
+
+    if let Err(e) = storage::write(&hash, &bytes).await {
+        repo.delete(attachment.id).await?;
+
+        return Err(e.into());
+    }
+
+    Ok((StatusCode::CREATED, Json(attachment)))
+}
+
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    Path((_, transaction_id)): Path<(String, String)>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = AttachmentRepo::new(&state.db);
+
+    let transaction_id = owned_transaction(&state, auth.user_id, transaction_id).await?;
+
+    let attachments = repo.list(transaction_id).await?;
+
+    Ok(Json(attachments))
+}
+
+pub async fn download(
+    State(state): State<Arc<ApiState>>,
+    Path((_, transaction_id, attachment_id)): Path<(String, String, String)>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = AttachmentRepo::new(&state.db);
+
+    let transaction_id = owned_transaction(&state, auth.user_id, transaction_id).await?;
+    let attachment_id = RecordId::from_table_key("attachment", attachment_id);
+
+    let attachment = repo.get(transaction_id, attachment_id).await?;
+    let bytes = storage::read(&attachment.hash).await?;
+
+    let disposition = format!(
+        "inline; filename=\"{}\"",
+        attachment.name.replace(['"', '\\', '\r', '\n'], "_")
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, attachment.mime),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        bytes,
+    ))
+}
+
+pub async fn delete(
+    State(state): State<Arc<ApiState>>,
+    Path((_, transaction_id, attachment_id)): Path<(String, String, String)>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = AttachmentRepo::new(&state.db);
+
+    let transaction_id = owned_transaction(&state, auth.user_id, transaction_id).await?;
+    let attachment_id = RecordId::from_table_key("attachment", attachment_id);
+
+    let attachment = repo.get(transaction_id, attachment_id).await?;
+
+    repo.delete(attachment.id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
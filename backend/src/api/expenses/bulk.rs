@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use axum::{Json, extract::State, response::IntoResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use surrealdb::RecordId;
+
+use crate::{
+    api::{ApiError, ApiState, auth::extractor::AuthUser},
+    db::repo::{CategoryRepo, TagRepo, transaction_repo::TransactionRepo},
+    models::BulkAction,
+};
+
+const MAX_OPERATIONS: usize = 500;
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum ActionPayload {
+    Delete,
+    MoveCategory { category: String },
+    SetNote { note: Option<String> },
+    AddTag { tag: String },
+    ShiftDate { days: i64 },
+}
+
+#[derive(Deserialize)]
+pub struct OperationPayload {
+    transaction: String,
+    #[serde(flatten)]
+    action: ActionPayload,
+}
+
+#[derive(Deserialize)]
+pub struct BulkPayload {
+    operations: Vec<OperationPayload>,
+}
+
+#[derive(Serialize)]
+pub struct OperationResult {
+    transaction: String,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Resolves an operation's action against the user's records, returning why it
+/// was rejected otherwise. Ownership lookups are cached in `owned` since bulk
+/// requests tend to repeat the same few categories and tags.
+async fn owned_action(
+    state: &ApiState,
+    user_id: RecordId,
+    action: ActionPayload,
+    owned: &mut Vec<(RecordId, bool)>,
+) -> Result<Result<BulkAction, String>, ApiError> {
+    let (id, message) = match action {
+        ActionPayload::Delete => return Ok(Ok(BulkAction::Delete)),
+        ActionPayload::SetNote { note } => return Ok(Ok(BulkAction::SetNote(note))),
+        ActionPayload::ShiftDate { days } => return Ok(Ok(BulkAction::ShiftDate(days))),
+        ActionPayload::MoveCategory { category } => (
+            RecordId::from_table_key("category", category),
+            "User does not own this category",
+        ),
+        ActionPayload::AddTag { tag } => (
+            RecordId::from_table_key("tag", tag),
+            "User does not own this tag",
+        ),
+    };
+
+    let is_owned = match owned.iter().find(|(owned_id, _)| *owned_id == id) {
+        Some((_, is_owned)) => *is_owned,
+        None => {
+            let is_owned = if id.table() == "category" {
+                CategoryRepo::new(&state.db)
+                    .user_owns(user_id, id.clone())
+                    .await?
+            } else {
+                TagRepo::new(&state.db)
+                    .user_owns(user_id, id.clone())
+                    .await?
+            };
+
+            owned.push((id.clone(), is_owned));
+            is_owned
+        }
+    };
+
+    if !is_owned {
+        return Ok(Err(message.into()));
+    }
+
+    Ok(Ok(if id.table() == "category" {
+        BulkAction::MoveCategory(id)
+    } else {
+        BulkAction::AddTag(id)
+    }))
+}
+
+/// Applies a list of operations in one database transaction. Operations on
+/// records the user doesn't own or on reconciled transactions are reported as
+/// failed and skipped; the rest succeed or fail together.
+pub async fn bulk(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    Json(payload): Json<BulkPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    if payload.operations.is_empty() || payload.operations.len() > MAX_OPERATIONS {
+        return Err(ApiError::Validation(json!({
+            "operations": format!("Between 1 and {MAX_OPERATIONS} operations are allowed")
+        })));
+    }
+
+    let repo = TransactionRepo::new(&state.db);
+
+    let owned_transactions = repo
+        .user_owned(
+            auth.user_id.clone(),
+            payload
+                .operations
+                .iter()
+                .map(|operation| RecordId::from_table_key("transaction", &operation.transaction))
+                .collect(),
+        )
+        .await?;
+
+    let locked = repo.reconciled(owned_transactions.clone()).await?;
+
+    let mut owned = Vec::new();
+    let mut operations = Vec::with_capacity(payload.operations.len());
+    let mut results = Vec::with_capacity(payload.operations.len());
+
+    for operation in payload.operations {
+        let transaction_id = RecordId::from_table_key("transaction", &operation.transaction);
+
+        let action = if !owned_transactions.contains(&transaction_id) {
+            Err("User does not own this transaction".into())
+        } else if locked.contains(&transaction_id) {
+            Err("Reconciled transactions cannot be changed".into())
+        } else {
+            owned_action(&state, auth.user_id.clone(), operation.action, &mut owned).await?
+        };
+
+        results.push(OperationResult {
+            transaction: operation.transaction,
+            ok: action.is_ok(),
+            error: action.as_ref().err().cloned(),
+        });
+
+        if let Ok(action) = action {
+            operations.push((transaction_id, action));
+        }
+    }
+
+    if !operations.is_empty() {
+        repo.bulk(auth.user_id, operations).await?;
+    }
+
+    Ok(Json(results))
+}
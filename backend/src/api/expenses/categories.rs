@@ -12,20 +12,53 @@ use surrealdb::RecordId;
 
 use crate::{
     api::{ApiError, ApiState, auth::extractor::AuthUser},
+    category_tree,
     db::{DbError, repo::CategoryRepo},
-    models::Category,
+    models::{Category, serialize_option_record_id},
 };
 
 #[derive(Deserialize)]
 pub struct ItemPayload {
     name: String,
     icon: String,
+    /// The category this one is nested under, if any.
+    parent: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct EditedCategory {
     name: String,
     icon: String,
+    #[serde(serialize_with = "serialize_option_record_id")]
+    parent: Option<RecordId>,
+}
+
+/// Checks the user owns `parent` and that nesting `category_id` (`None` for
+/// a new category) under it neither makes a cycle nor nests too deep.
+async fn valid_parent(
+    state: &ApiState,
+    user_id: RecordId,
+    category_id: Option<&RecordId>,
+    parent: Option<String>,
+) -> Result<Option<RecordId>, ApiError> {
+    let Some(parent) = parent else {
+        return Ok(None);
+    };
+
+    let repo = CategoryRepo::new(&state.db);
+
+    let parent_id = RecordId::from_table_key("category", parent);
+
+    if !(repo.user_owns(user_id.clone(), parent_id.clone()).await?) {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this category".into(),
+        )));
+    }
+
+    category_tree::check_parent(&repo.parents(user_id).await?, category_id, &parent_id)
+        .map_err(|e| ApiError::Validation(json!({ "parent": e })))?;
+
+    Ok(Some(parent_id))
 }
 
 pub async fn create(
@@ -39,22 +72,33 @@ pub async fn create(
     let name = payload.name;
     let icon = payload.icon;
 
-    if repo.exists(user_id.clone(), name.clone()).await? {
+    let parent_id = valid_parent(&state, user_id.clone(), None, payload.parent).await?;
+
+    if repo
+        .exists(user_id.clone(), name.clone(), parent_id.clone())
+        .await?
+    {
         return Err(ApiError::AlreadyExists(json!(
             {"name": "Category with this name already exists"}
         )));
     }
 
-    let category_id = repo.create(user_id, name.clone(), icon.clone()).await?;
+    let category_id = repo
+        .create(user_id, name.clone(), icon.clone(), parent_id.clone())
+        .await?;
 
     Ok((
         StatusCode::CREATED,
         Json(Category {
             id: category_id,
+            parent: parent_id,
             name,
             icon,
             amount: 0.0,
             transactions: 0,
+            total_amount: 0.0,
+            total_transactions: 0,
+            children: Vec::new(),
         }),
     ))
 }
@@ -78,8 +122,16 @@ pub async fn edit(
         )));
     }
 
+    let parent_id =
+        valid_parent(&state, user_id.clone(), Some(&category_id), payload.parent).await?;
+
     if repo
-        .exists_excluding(user_id.clone(), name.clone(), category_id.clone())
+        .exists_excluding(
+            user_id.clone(),
+            name.clone(),
+            parent_id.clone(),
+            category_id.clone(),
+        )
         .await?
     {
         return Err(ApiError::AlreadyExists(json!(
@@ -87,9 +139,17 @@ pub async fn edit(
         )));
     }
 
-    repo.edit(category_id, name.clone(), icon.clone()).await?;
+    repo.edit(category_id, name.clone(), icon.clone(), parent_id.clone())
+        .await?;
 
-    Ok((StatusCode::OK, Json(EditedCategory { name, icon })))
+    Ok((
+        StatusCode::OK,
+        Json(EditedCategory {
+            name,
+            icon,
+            parent: parent_id,
+        }),
+    ))
 }
 
 pub async fn delete(
@@ -108,7 +168,124 @@ pub async fn delete(
         )));
     }
 
+    if repo.has_children(category_id.clone()).await? {
+        return Err(ApiError::Validation(json!(
+            {"category": "Move or delete its subcategories first"}
+        )));
+    }
+
     repo.delete(category_id).await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+pub async fn restore(
+    State(state): State<Arc<ApiState>>,
+    Path(category_id): Path<String>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = CategoryRepo::new(&state.db);
+
+    let user_id = auth.user_id;
+    let category_id = RecordId::from_table_key("category", category_id);
+
+    if !(repo
+        .user_owns_trashed(user_id.clone(), category_id.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this category".into(),
+        )));
+    }
+
+    let name = repo.name(category_id.clone()).await?;
+
+    // A parent that has since been trashed or filled up leaves the category
+    // to come back at the top level.
+    let parent_id = match repo.live_parent(category_id.clone()).await? {
+        Some(parent_id) => category_tree::check_parent(
+            &repo.parents(user_id.clone()).await?,
+            Some(&category_id),
+            &parent_id,
+        )
+        .ok()
+        .map(|_| parent_id),
+        None => None,
+    };
+
+    if repo
+        .exists_excluding(user_id, name, parent_id.clone(), category_id.clone())
+        .await?
+    {
+        return Err(ApiError::AlreadyExists(json!(
+            {"name": "Category with this name already exists"}
+        )));
+    }
+
+    repo.restore(category_id, parent_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Merges the category into `target`, which takes over its transactions,
+/// subcategories, recurring transactions, installment plans, rules and
+/// templates. The merged category is deleted for good rather than trashed;
+/// its trashed subcategories come back at the top level if restored.
+pub async fn merge(
+    State(state): State<Arc<ApiState>>,
+    Path((category_id, target_id)): Path<(String, String)>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = CategoryRepo::new(&state.db);
+
+    let user_id = auth.user_id;
+    let category_id = RecordId::from_table_key("category", category_id);
+    let target_id = RecordId::from_table_key("category", target_id);
+
+    for id in [&category_id, &target_id] {
+        if !(repo.user_owns(user_id.clone(), id.clone()).await?) {
+            return Err(ApiError::Db(DbError::NotFound(
+                "User does not own this category".into(),
+            )));
+        }
+    }
+
+    if category_id == target_id {
+        return Err(ApiError::Validation(
+            json!({"target": "A category cannot be merged into itself"}),
+        ));
+    }
+
+    let parents = repo.parents(user_id.clone()).await?;
+
+    if category_tree::is_descendant(&parents, &target_id, &category_id) {
+        return Err(ApiError::Validation(json!(
+            {"target": "A category cannot be merged into one of its subcategories"}
+        )));
+    }
+
+    // The subcategories move under the target, so they have to fit there the
+    // same way they would when moved one by one.
+    for child in repo.children(category_id.clone()).await? {
+        category_tree::check_parent(&parents, Some(&child.id), &target_id)
+            .map_err(|e| ApiError::Validation(json!({ "target": e })))?;
+
+        if repo
+            .exists_excluding(
+                user_id.clone(),
+                child.name,
+                Some(target_id.clone()),
+                child.id,
+            )
+            .await?
+        {
+            return Err(ApiError::AlreadyExists(json!(
+                {"name": "The target already has a subcategory with this name"}
+            )));
+        }
+    }
+
+    repo.merge(category_id, target_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+use surrealdb::RecordId;
+
+use crate::{
+    api::{
+        ApiError, ApiState, auth::extractor::AuthUser, expenses::transactions::owned_transaction,
+    },
+    db::{DbError, repo::CommentRepo},
+};
+
+const MAX_COMMENT_LENGTH: usize = 2000;
+
+#[derive(Deserialize)]
+pub struct ItemPayload {
+    body: String,
+    /// The comment this one replies to.
+    parent: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct EditPayload {
+    body: String,
+}
+
+fn validate_body(body: &str) -> Result<String, ApiError> {
+    let body = body.trim();
+
+    if body.is_empty() {
+        return Err(ApiError::Validation(
+            json!({"body": "Comment cannot be empty"}),
+        ));
+    }
+
+    if body.chars().count() > MAX_COMMENT_LENGTH {
+        return Err(ApiError::Validation(json!(
+            {"body": format!("Comment must be at most {MAX_COMMENT_LENGTH} characters")}
+        )));
+    }
+
+    Ok(body.to_string())
+}
+
+async fn owned_comment(
+    state: &ApiState,
+    transaction_id: RecordId,
+    comment_id: String,
+) -> Result<RecordId, ApiError> {
+    let comment_id = RecordId::from_table_key("transaction_comment", comment_id);
+
+    if !(CommentRepo::new(&state.db)
+        .belongs_to(transaction_id, comment_id.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(json!(
+            {"comment": "No comment found for this transaction"}
+        ))));
+    }
+
+    Ok(comment_id)
+}
+
+/// Only a comment's author may change or remove it.
+async fn authored_comment(
+    state: &ApiState,
+    user_id: RecordId,
+    transaction_id: RecordId,
+    comment_id: String,
+) -> Result<RecordId, ApiError> {
+    let comment_id = owned_comment(state, transaction_id, comment_id).await?;
+
+    if !(CommentRepo::new(&state.db)
+        .is_author(user_id, comment_id.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User did not write this comment".into(),
+        )));
+    }
+
+    Ok(comment_id)
+}
+
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    Path((_, transaction_id)): Path<(String, String)>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let transaction_id = owned_transaction(&state, auth.user_id, transaction_id).await?;
+
+    let comments = CommentRepo::new(&state.db).list(transaction_id).await?;
+
+    Ok(Json(comments))
+}
+
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    Path((_, transaction_id)): Path<(String, String)>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = CommentRepo::new(&state.db);
+
+    let transaction_id = owned_transaction(&state, auth.user_id.clone(), transaction_id).await?;
+    let body = validate_body(&payload.body)?;
+
+    let parent_id = match payload.parent {
+        Some(parent) => Some(owned_comment(&state, transaction_id.clone(), parent).await?),
+        None => None,
+    };
+
+    let comment_id = repo
+        .create(transaction_id, auth.user_id, parent_id, body)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(repo.get(comment_id).await?)))
+}
+
+pub async fn edit(
+    State(state): State<Arc<ApiState>>,
+    Path((_, transaction_id, comment_id)): Path<(String, String, String)>,
+    auth: AuthUser,
+    Json(payload): Json<EditPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = CommentRepo::new(&state.db);
+
+    let transaction_id = owned_transaction(&state, auth.user_id.clone(), transaction_id).await?;
+    let comment_id = authored_comment(&state, auth.user_id, transaction_id, comment_id).await?;
+    let body = validate_body(&payload.body)?;
+
+    repo.edit(comment_id.clone(), body).await?;
+
+    Ok(Json(repo.get(comment_id).await?))
+}
+
+/// Deletes a comment together with the replies to it.
+pub async fn delete(
+    State(state): State<Arc<ApiState>>,
+    Path((_, transaction_id, comment_id)): Path<(String, String, String)>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let transaction_id = owned_transaction(&state, auth.user_id.clone(), transaction_id).await?;
+    let comment_id = authored_comment(&state, auth.user_id, transaction_id, comment_id).await?;
+
+    CommentRepo::new(&state.db).delete(comment_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
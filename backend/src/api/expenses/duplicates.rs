@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+use surrealdb::RecordId;
+
+use crate::{
+    api::{
+        ApiError, ApiState,
+        auth::extractor::AuthUser,
+        defs::{DateRange, TimezoneQuery},
+        expenses::refunds::check_merged_refunds,
+    },
+    db::{
+        DbError,
+        repo::{DuplicateRepo, transaction_repo::TransactionRepo},
+    },
+    duplicates,
+};
+
+#[derive(Deserialize)]
+pub struct PairPayload {
+    original: String,
+    duplicate: String,
+}
+
+async fn owned_pair(
+    state: &ApiState,
+    user_id: RecordId,
+    payload: PairPayload,
+) -> Result<(RecordId, RecordId), ApiError> {
+    let repo = TransactionRepo::new(&state.db);
+
+    if payload.original == payload.duplicate {
+        return Err(ApiError::Validation(json!(
+            {"duplicate": "A transaction can't be a duplicate of itself"}
+        )));
+    }
+
+    let original = RecordId::from_table_key("transaction", payload.original);
+    let duplicate = RecordId::from_table_key("transaction", payload.duplicate);
+
+    for transaction_id in [&original, &duplicate] {
+        if !(repo
+            .user_owns(user_id.clone(), transaction_id.clone())
+            .await?)
+        {
+            return Err(ApiError::Db(DbError::NotFound(
+                "User does not own this transaction".into(),
+            )));
+        }
+    }
+
+    Ok((original, duplicate))
+}
+
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    Query(range): Query<DateRange>,
+    Query(tz): Query<TimezoneQuery>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let tz = tz.resolve(&state, auth.user_id.clone()).await?;
+    let (start, end) = range.resolve(tz)?;
+
+    let transactions = TransactionRepo::new(&state.db)
+        .summaries(auth.user_id, start, end)
+        .await?;
+
+    let dismissed = DuplicateRepo::new(&state.db)
+        .dismissed(transactions.iter().map(|t| t.id.clone()).collect())
+        .await?;
+
+    Ok(Json(duplicates::find_pairs(transactions, &dismissed)))
+}
+
+pub async fn merge(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    Json(payload): Json<PairPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (original, duplicate) = owned_pair(&state, auth.user_id.clone(), payload).await?;
+
+    if !TransactionRepo::new(&state.db)
+        .reconciled(vec![original.clone(), duplicate.clone()])
+        .await?
+        .is_empty()
+    {
+        return Err(ApiError::Locked(json!(
+            {"transaction": "Reconciled transactions cannot be merged"}
+        )));
+    }
+
+    check_merged_refunds(&state, original.clone(), duplicate.clone()).await?;
+
+    DuplicateRepo::new(&state.db)
+        .merge(auth.user_id, original, duplicate)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn dismiss(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    Json(payload): Json<PairPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (original, duplicate) = owned_pair(&state, auth.user_id, payload).await?;
+
+    DuplicateRepo::new(&state.db)
+        .dismiss(original, duplicate)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    api::{
+        ApiError, ApiState,
+        auth::extractor::AuthUser,
+        defs::{DateRange, PendingFilter, TimezoneQuery},
+    },
+    clusters::{self, MAX_CLUSTER_ZOOM},
+    db::repo::transaction_repo::TransactionRepo,
+    models::BoundingBox,
+};
+
+const MAX_ZOOM: u8 = 22;
+
+#[derive(Deserialize)]
+pub struct MapQuery {
+    /// `west,south,east,north` in degrees.
+    bbox: Option<String>,
+    /// Map zoom level; transactions are clustered below `MAX_CLUSTER_ZOOM`.
+    zoom: Option<u8>,
+}
+
+fn parse_bbox(bbox: &str) -> Result<BoundingBox, ApiError> {
+    let invalid = || {
+        ApiError::Validation(json!(
+            {"bbox": "Expected west,south,east,north in degrees"}
+        ))
+    };
+
+    let edges = bbox
+        .split(',')
+        .map(|edge| edge.trim().parse::<f64>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let [west, south, east, north] = edges[..] else {
+        return Err(invalid());
+    };
+
+    if [west, east]
+        .iter()
+        .any(|lon| !(-180.0..=180.0).contains(lon))
+        || [south, north]
+            .iter()
+            .any(|lat| !(-90.0..=90.0).contains(lat))
+        || south > north
+    {
+        return Err(invalid());
+    }
+
+    Ok(BoundingBox {
+        west,
+        south,
+        east,
+        north,
+    })
+}
+
+/// Returns the user's located transactions in the date range as a GeoJSON
+/// `FeatureCollection`, clustered by grid cell when zoomed out.
+pub async fn map(
+    State(state): State<Arc<ApiState>>,
+    Query(range): Query<DateRange>,
+    Query(query): Query<MapQuery>,
+    Query(pending): Query<PendingFilter>,
+    Query(tz): Query<TimezoneQuery>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let zoom = query.zoom.unwrap_or(MAX_CLUSTER_ZOOM);
+
+    if zoom > MAX_ZOOM {
+        return Err(ApiError::Validation(
+            json!({"zoom": format!("Zoom must be at most {MAX_ZOOM}")}),
+        ));
+    }
+
+    let bbox = query.bbox.as_deref().map(parse_bbox).transpose()?;
+
+    let tz = tz.resolve(&state, auth.user_id.clone()).await?;
+    let (start, end) = range.resolve(tz)?;
+
+    let transactions = TransactionRepo::new(&state.db)
+        .located(auth.user_id, start, end, bbox, pending.include_pending)
+        .await?;
+
+    Ok(Json(clusters::features(transactions, zoom)))
+}
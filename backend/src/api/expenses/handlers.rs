@@ -7,20 +7,53 @@ use axum::{
 };
 
 use crate::{
-    api::{ApiError, ApiState, auth::extractor::AuthUser, defs::DateRange},
-    db::repo::CategoryRepo,
+    api::{
+        ApiError, ApiState,
+        auth::extractor::AuthUser,
+        defs::{DateRange, PendingFilter, TagFilter, TimezoneQuery},
+    },
+    db::repo::{CategoryRepo, PayeeRepo},
 };
 
 pub async fn get_expenses_overview(
     State(state): State<Arc<ApiState>>,
     auth: AuthUser,
     Query(range): Query<DateRange>,
+    Query(filter): Query<TagFilter>,
+    Query(pending): Query<PendingFilter>,
+    Query(tz): Query<TimezoneQuery>,
 ) -> Result<impl IntoResponse, ApiError> {
+    let tz = tz.resolve(&state, auth.user_id.clone()).await?;
+    let (start, end) = range.resolve(tz)?;
+
     let repo = CategoryRepo::new(&state.db);
 
     let expenses = repo
-        .get_expenses_overview(auth.user_id, range.start, range.end)
+        .get_expenses_overview(
+            auth.user_id,
+            start,
+            end,
+            filter.tag_id(),
+            pending.include_pending,
+            tz,
+        )
         .await?;
 
     Ok(Json(expenses))
 }
+
+pub async fn get_payees_overview(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    Query(range): Query<DateRange>,
+    Query(tz): Query<TimezoneQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let tz = tz.resolve(&state, auth.user_id.clone()).await?;
+    let (start, end) = range.resolve(tz)?;
+
+    let repo = PayeeRepo::new(&state.db);
+
+    let payees = repo.totals(auth.user_id, start, end).await?;
+
+    Ok(Json(payees))
+}
@@ -0,0 +1,538 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Multipart, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use futures::future::join_all;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::json;
+use surrealdb::RecordId;
+
+use crate::{
+    api::{ApiError, ApiState, auth::extractor::AuthUser},
+    category_tree,
+    db::{
+        DbError,
+        repo::{CategoryRepo, RuleRepo, transaction_repo::TransactionRepo},
+    },
+    import::{
+        self, ImportError, ImportRow, ParsedImport, csv::CsvOptions, ofx::OfxOptions,
+        qif::QifOptions,
+    },
+    models::{CategoryName, TransactionDraft, serialize_record_ids},
+    rules::{RuleSet, Subject},
+};
+
+const BATCH_SIZE: usize = 50;
+const NEW_CATEGORY_ICON: &str = "FaTag";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewRow {
+    #[serde(flatten)]
+    row: ImportRow,
+    category_id: Option<String>,
+    new_category: bool,
+    #[serde(serialize_with = "serialize_record_ids")]
+    tags: Vec<RecordId>,
+}
+
+#[derive(Serialize)]
+pub struct Preview {
+    rows: Vec<PreviewRow>,
+    errors: Vec<ImportError>,
+    /// Line numbers of the credit entries left out of the import.
+    skipped: Vec<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Committed {
+    imported: usize,
+    created_categories: Vec<CategoryName>,
+    errors: Vec<ImportError>,
+    skipped: Vec<usize>,
+}
+
+/// Where an imported row's transaction will be filed.
+enum Target {
+    Existing(RecordId),
+    /// A category path, whose missing levels are created on commit.
+    New(Vec<String>),
+}
+
+struct ResolvedRow {
+    row: ImportRow,
+    target: Target,
+    tags: Vec<RecordId>,
+}
+
+/// Reads the `file` and `options` parts of an import upload, decoding the
+/// latter as JSON.
+async fn read_upload<T: DeserializeOwned>(
+    mut multipart: Multipart,
+) -> Result<(Bytes, T), ApiError> {
+    let invalid = |field: &str, message: String| ApiError::Validation(json!({ field: message }));
+
+    let mut file = None;
+    let mut options = None;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| invalid("file", e.body_text()))?
+    {
+        match field.name() {
+            Some("file") => {
+                file = Some(
+                    field
+                        .bytes()
+                        .await
+                        .map_err(|e| invalid("file", e.body_text()))?,
+                )
+            }
+            Some("options") => {
+                options = Some(
+                    field
+                        .bytes()
+                        .await
+                        .map_err(|e| invalid("options", e.body_text()))?,
+                )
+            }
+            _ => continue,
+        }
+    }
+
+    let file = file.ok_or_else(|| invalid("file", "A file is required".into()))?;
+    let options = match options {
+        Some(options) => serde_json::from_slice(&options),
+        None => serde_json::from_str("{}"),
+    }
+    .map_err(|e| invalid("options", e.to_string()))?;
+
+    Ok((file, options))
+}
+
+/// Matches each row's category name or `Parent > Child` path against the
+/// user's categories through `category_tree::find`; unknown ones are created
+/// when `create_categories` is set. Rows without one are categorized by the
+/// user's rules, falling back to `default_category`.
+async fn resolve(
+    state: &ApiState,
+    user_id: RecordId,
+    parsed: ParsedImport,
+    default_category: Option<String>,
+    create_categories: bool,
+) -> Result<(Vec<ResolvedRow>, Vec<ImportError>), ApiError> {
+    let category_repo = CategoryRepo::new(&state.db);
+
+    let default_category = match default_category {
+        Some(category_id) => {
+            let category_id = RecordId::from_table_key("category", category_id);
+
+            if !(category_repo
+                .user_owns(user_id.clone(), category_id.clone())
+                .await?)
+            {
+                return Err(ApiError::Db(DbError::NotFound(
+                    "User does not own this category".into(),
+                )));
+            }
+
+            Some(category_id)
+        }
+        None => None,
+    };
+
+    let categories = category_repo.list_names(user_id.clone()).await?;
+    let rules = RuleSet::new(
+        RuleRepo::new(&state.db)
+            .list_active(user_id.clone())
+            .await?,
+    );
+
+    let ParsedImport {
+        rows, mut errors, ..
+    } = parsed;
+    let mut resolved = Vec::with_capacity(rows.len());
+
+    let fitids = rows.iter().filter_map(|row| row.fitid.clone()).collect();
+    let mut seen = TransactionRepo::new(&state.db)
+        .imported_fitids(user_id, fitids)
+        .await?;
+
+    for row in rows {
+        if let Some(fitid) = &row.fitid {
+            if seen.contains(fitid) {
+                errors.push(ImportError {
+                    line: row.line,
+                    message: format!("Transaction {fitid} was already imported"),
+                });
+                continue;
+            }
+
+            seen.push(fitid.clone());
+        }
+
+        let outcome = rules.evaluate(&Subject {
+            amount: row.amount,
+            note: row.note.as_deref(),
+            payee: None,
+        });
+
+        let target = match (row.category.as_deref(), outcome.category, &default_category) {
+            (Some(name), _, _) => match category_tree::find(&categories, name) {
+                Ok(Some(category)) => Target::Existing(category.id.clone()),
+                Ok(None) if create_categories => match category_tree::new_path(name) {
+                    Ok(levels) => Target::New(levels),
+                    Err(message) => {
+                        errors.push(ImportError {
+                            line: row.line,
+                            message: message.into(),
+                        });
+                        continue;
+                    }
+                },
+                Err(message) => {
+                    errors.push(ImportError {
+                        line: row.line,
+                        message,
+                    });
+                    continue;
+                }
+                Ok(None) => {
+                    errors.push(ImportError {
+                        line: row.line,
+                        message: format!("Unknown category \"{name}\""),
+                    });
+                    continue;
+                }
+            },
+            (None, Some(category_id), _) => Target::Existing(category_id),
+            (None, None, Some(category_id)) => Target::Existing(category_id.clone()),
+            (None, None, None) => {
+                errors.push(ImportError {
+                    line: row.line,
+                    message: "Row has no category and no default category was given".into(),
+                });
+                continue;
+            }
+        };
+
+        resolved.push(ResolvedRow {
+            row,
+            target,
+            tags: outcome.tags,
+        });
+    }
+
+    errors.sort_by_key(|error| error.line);
+
+    Ok((resolved, errors))
+}
+
+fn preview(resolved: Vec<ResolvedRow>, errors: Vec<ImportError>, skipped: Vec<usize>) -> Preview {
+    let rows = resolved
+        .into_iter()
+        .map(|ResolvedRow { row, target, tags }| match target {
+            Target::Existing(category_id) => PreviewRow {
+                row,
+                category_id: Some(category_id.key().to_string()),
+                new_category: false,
+                tags,
+            },
+            Target::New(_) => PreviewRow {
+                row,
+                category_id: None,
+                new_category: true,
+                tags,
+            },
+        })
+        .collect();
+
+    Preview {
+        rows,
+        errors,
+        skipped,
+    }
+}
+
+/// Creates any missing categories, nesting each level of a path under the
+/// previous one, then the transactions themselves through
+/// `TransactionRepo::create_imported`, `BATCH_SIZE` at a time.
+async fn commit(
+    state: &ApiState,
+    user_id: RecordId,
+    resolved: Vec<ResolvedRow>,
+    mut errors: Vec<ImportError>,
+    skipped: Vec<usize>,
+) -> Result<Committed, ApiError> {
+    let category_repo = CategoryRepo::new(&state.db);
+    let transaction_repo = TransactionRepo::new(&state.db);
+
+    let mut categories = category_repo.list_names(user_id.clone()).await?;
+    let mut created_categories = Vec::<CategoryName>::new();
+    let mut drafts = Vec::with_capacity(resolved.len());
+
+    for ResolvedRow { row, target, tags } in resolved {
+        let category_id = match target {
+            Target::Existing(category_id) => category_id,
+            Target::New(levels) => {
+                let mut parent_id = None::<RecordId>;
+
+                for name in levels {
+                    let existing = categories.iter().find(|category| {
+                        category.parent == parent_id
+                            && category.name.to_lowercase() == name.to_lowercase()
+                    });
+
+                    let category_id = match existing {
+                        Some(category) => category.id.clone(),
+                        None => {
+                            let category_id = category_repo
+                                .create(
+                                    user_id.clone(),
+                                    name.clone(),
+                                    NEW_CATEGORY_ICON.into(),
+                                    parent_id.clone(),
+                                )
+                                .await?;
+
+                            let category = CategoryName {
+                                id: category_id.clone(),
+                                name,
+                                parent: parent_id,
+                            };
+
+                            categories.push(category.clone());
+                            created_categories.push(category);
+
+                            category_id
+                        }
+                    };
+
+                    parent_id = Some(category_id);
+                }
+
+                parent_id.expect("category paths have at least one level")
+            }
+        };
+
+        drafts.push((
+            row.line,
+            category_id,
+            row.fitid,
+            TransactionDraft {
+                account: None,
+                amount: row.amount,
+                note: row.note,
+                date: row.date,
+                splits: Vec::new(),
+                tags,
+                payee: None,
+            },
+        ));
+    }
+
+    let mut imported = 0;
+
+    for batch in drafts.chunks(BATCH_SIZE) {
+        let results = join_all(batch.iter().map(|(_, category_id, fitid, draft)| {
+            transaction_repo.create_imported(category_id.clone(), draft.clone(), fitid.clone())
+        }))
+        .await;
+
+        for ((line, _, _, _), result) in batch.iter().zip(results) {
+            match result {
+                Ok(_) => imported += 1,
+                Err(e) => {
+                    tracing::error!("Failed to import line {line}: {e}");
+                    errors.push(ImportError {
+                        line: *line,
+                        message: "Transaction could not be created".into(),
+                    });
+                }
+            }
+        }
+    }
+
+    errors.sort_by_key(|error| error.line);
+
+    Ok(Committed {
+        imported,
+        created_categories,
+        errors,
+        skipped,
+    })
+}
+
+async fn preview_import(
+    state: &ApiState,
+    user_id: RecordId,
+    mut parsed: ParsedImport,
+    default_category: Option<String>,
+    create_categories: bool,
+) -> Result<Json<Preview>, ApiError> {
+    let skipped = std::mem::take(&mut parsed.skipped);
+
+    let (resolved, errors) =
+        resolve(state, user_id, parsed, default_category, create_categories).await?;
+
+    Ok(Json(preview(resolved, errors, skipped)))
+}
+
+async fn commit_import(
+    state: &ApiState,
+    user_id: RecordId,
+    mut parsed: ParsedImport,
+    default_category: Option<String>,
+    create_categories: bool,
+) -> Result<(StatusCode, Json<Committed>), ApiError> {
+    let skipped = std::mem::take(&mut parsed.skipped);
+
+    let (resolved, errors) = resolve(
+        state,
+        user_id.clone(),
+        parsed,
+        default_category,
+        create_categories,
+    )
+    .await?;
+
+    let committed = commit(state, user_id, resolved, errors, skipped).await?;
+
+    Ok((StatusCode::CREATED, Json(committed)))
+}
+
+async fn parse_csv(multipart: Multipart) -> Result<(ParsedImport, CsvOptions), ApiError> {
+    let (file, options) = read_upload::<CsvOptions>(multipart).await?;
+
+    let parsed = import::csv::parse(&file, &options)
+        .map_err(|e| ApiError::Validation(json!({ "options": e })))?;
+
+    Ok((parsed, options))
+}
+
+async fn parse_ofx(multipart: Multipart) -> Result<(ParsedImport, OfxOptions), ApiError> {
+    let (file, options) = read_upload::<OfxOptions>(multipart).await?;
+
+    let parsed =
+        import::ofx::parse(&file).map_err(|e| ApiError::Validation(json!({ "file": e })))?;
+
+    Ok((parsed, options))
+}
+
+async fn parse_qif(multipart: Multipart) -> Result<(ParsedImport, QifOptions), ApiError> {
+    let (file, options) = read_upload::<QifOptions>(multipart).await?;
+
+    let parsed = import::qif::parse(&file, &options)
+        .map_err(|e| ApiError::Validation(json!({ "file": e })))?;
+
+    Ok((parsed, options))
+}
+
+pub async fn csv_preview(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    multipart: Multipart,
+) -> Result<impl IntoResponse, ApiError> {
+    let (parsed, options) = parse_csv(multipart).await?;
+
+    preview_import(
+        &state,
+        auth.user_id,
+        parsed,
+        options.default_category,
+        options.create_categories,
+    )
+    .await
+}
+
+pub async fn csv_commit(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    multipart: Multipart,
+) -> Result<impl IntoResponse, ApiError> {
+    let (parsed, options) = parse_csv(multipart).await?;
+
+    commit_import(
+        &state,
+        auth.user_id,
+        parsed,
+        options.default_category,
+        options.create_categories,
+    )
+    .await
+}
+
+pub async fn ofx_preview(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    multipart: Multipart,
+) -> Result<impl IntoResponse, ApiError> {
+    let (parsed, options) = parse_ofx(multipart).await?;
+
+    preview_import(
+        &state,
+        auth.user_id,
+        parsed,
+        options.default_category,
+        false,
+    )
+    .await
+}
+
+pub async fn ofx_commit(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    multipart: Multipart,
+) -> Result<impl IntoResponse, ApiError> {
+    let (parsed, options) = parse_ofx(multipart).await?;
+
+    commit_import(
+        &state,
+        auth.user_id,
+        parsed,
+        options.default_category,
+        false,
+    )
+    .await
+}
+
+pub async fn qif_preview(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    multipart: Multipart,
+) -> Result<impl IntoResponse, ApiError> {
+    let (parsed, options) = parse_qif(multipart).await?;
+
+    preview_import(
+        &state,
+        auth.user_id,
+        parsed,
+        options.default_category,
+        options.create_categories,
+    )
+    .await
+}
+
+pub async fn qif_commit(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    multipart: Multipart,
+) -> Result<impl IntoResponse, ApiError> {
+    let (parsed, options) = parse_qif(multipart).await?;
+
+    commit_import(
+        &state,
+        auth.user_id,
+        parsed,
+        options.default_category,
+        options.create_categories,
+    )
+    .await
+}
@@ -0,0 +1,208 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+use surrealdb::{Datetime, RecordId};
+
+use crate::{
+    api::{ApiError, ApiState, auth::extractor::AuthUser, expenses::transactions::owned_account},
+    db::{
+        DbError,
+        repo::{CategoryRepo, InstallmentRepo, transaction_repo::TransactionRepo},
+    },
+    models::{Frequency, InstallmentPlan, RecurringSchedule, TransactionDraft},
+    recurrence,
+};
+
+const MAX_INSTALLMENTS: u32 = 120;
+
+#[derive(Deserialize)]
+pub struct ItemPayload {
+    pub total: f64,
+    pub count: u32,
+    pub start: Datetime,
+    pub note: Option<String>,
+    pub account: Option<String>,
+}
+
+/// Splits `total` into `count` installments rounded to cents, with the last
+/// one absorbing the rounding difference.
+fn installment_amounts(total: f64, count: u32) -> Vec<f64> {
+    let share = (total / f64::from(count) * 100.0).round() / 100.0;
+    let last = total - share * f64::from(count - 1);
+
+    (1..count)
+        .map(|_| share)
+        .chain(std::iter::once((last * 100.0).round() / 100.0))
+        .collect()
+}
+
+/// Creates the plan's installments, one transaction per occurrence of
+/// `schedule`, pushing each to `created` as soon as it exists.
+async fn add_installments(
+    state: &ApiState,
+    plan_id: &RecordId,
+    category_id: &RecordId,
+    account_id: &Option<RecordId>,
+    payload: &ItemPayload,
+    schedule: &RecurringSchedule,
+    created: &mut Vec<RecordId>,
+) -> Result<(), ApiError> {
+    let installment_repo = InstallmentRepo::new(&state.db);
+    let transaction_repo = TransactionRepo::new(&state.db);
+
+    for (n, amount) in (0..).zip(installment_amounts(payload.total, payload.count)) {
+        let Some(date) = recurrence::occurrence(schedule, n) else {
+            break;
+        };
+
+        let transaction_id = transaction_repo
+            .create(
+                category_id.clone(),
+                TransactionDraft {
+                    account: account_id.clone(),
+                    amount,
+                    note: payload.note.clone(),
+                    date: date.into(),
+                    splits: Vec::new(),
+                    tags: Vec::new(),
+                    payee: None,
+                },
+            )
+            .await?;
+
+        created.push(transaction_id.clone());
+
+        installment_repo
+            .link(plan_id.clone(), transaction_id, n + 1)
+            .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    Path(category_id): Path<String>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let category_repo = CategoryRepo::new(&state.db);
+    let installment_repo = InstallmentRepo::new(&state.db);
+
+    let category_id = RecordId::from_table_key("category", category_id);
+
+    if payload.total <= 0.0 {
+        return Err(ApiError::Validation(json!(
+            {"total": "Total must be greater than zero"}
+        )));
+    }
+
+    if !(2..=MAX_INSTALLMENTS).contains(&payload.count) {
+        return Err(ApiError::Validation(json!(
+            {"count": format!("Count must be between 2 and {MAX_INSTALLMENTS}")}
+        )));
+    }
+
+    if !(category_repo
+        .user_owns(auth.user_id.clone(), category_id.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this category".into(),
+        )));
+    }
+
+    let account_id = owned_account(&state, auth.user_id, payload.account.clone()).await?;
+
+    let schedule = RecurringSchedule {
+        frequency: Frequency::Monthly,
+        interval: 1,
+        start: payload.start.clone(),
+        until: None,
+        count: Some(payload.count),
+    };
+
+    let plan_id = installment_repo
+        .create(
+            category_id.clone(),
+            account_id.clone(),
+            payload.note.clone(),
+            payload.total,
+            payload.count,
+            payload.start.clone(),
+        )
+        .await?;
+
+    // A plan missing installments, or installments not linked to it, would
+    // misreport what is owed, so a failure part way through discards
+    // everything created so far.
+    let mut created = Vec::new();
+
+    if let Err(e) = add_installments(
+        &state,
+        &plan_id,
+        &category_id,
+        &account_id,
+        &payload,
+        &schedule,
+        &mut created,
+    )
+    .await
+    {
+        installment_repo.discard(plan_id, created).await?;
+
+        return Err(e);
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(InstallmentPlan {
+            id: plan_id,
+            category: category_id,
+            account: account_id,
+            note: payload.note,
+            total: payload.total,
+            count: payload.count,
+            start: payload.start,
+            paid: 0.0,
+            paid_count: 0,
+            remaining: payload.total,
+        }),
+    ))
+}
+
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let plans = InstallmentRepo::new(&state.db).list(auth.user_id).await?;
+
+    Ok(Json(plans))
+}
+
+pub async fn delete(
+    State(state): State<Arc<ApiState>>,
+    Path(plan_id): Path<String>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = InstallmentRepo::new(&state.db);
+
+    let plan_id = RecordId::from_table_key("installment_plan", plan_id);
+
+    if !(repo.user_owns(auth.user_id, plan_id.clone()).await?) {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this installment plan".into(),
+        )));
+    }
+
+    repo.delete(plan_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,40 +1,186 @@
+mod attachments;
+mod bulk;
 mod categories;
+mod comments;
+mod duplicates;
+mod geo;
 mod handlers;
+mod imports;
+mod installments;
+mod payees;
+mod quick_add;
+mod recurring;
+mod refunds;
+mod revisions;
+mod rules;
+mod search;
+mod tags;
+mod templates;
 mod transactions;
+mod trash;
+mod upcoming;
 
 use std::sync::Arc;
 
 use axum::{
-    Router, middleware,
+    Router,
+    extract::DefaultBodyLimit,
+    middleware,
     routing::{delete, get, patch, post},
 };
 
-use crate::api::{ApiState, auth::middleware::require_auth};
+use crate::{
+    api::{ApiState, auth::middleware::require_auth},
+    config::config,
+};
+
+/// Headroom on top of the attachment size limit for the multipart framing.
+const MULTIPART_OVERHEAD: usize = 16 * 1024;
+
+const IMPORT_BODY_LIMIT: usize = 10 * 1024 * 1024;
 
 pub fn router(state: Arc<ApiState>) -> Router<Arc<ApiState>> {
     let categories_router = Router::new().route("/create", post(categories::create));
     let category_router = Router::new()
         .route("/edit", patch(categories::edit))
-        .route("/delete", delete(categories::delete));
+        .route("/delete", delete(categories::delete))
+        .route("/restore", post(categories::restore))
+        .route("/merge-into/{id}", post(categories::merge));
+
+    let category_recurring_router = Router::new().route("/create", post(recurring::create));
+    let category_installments_router = Router::new().route("/create", post(installments::create));
+
+    let recurring_router = Router::new().route("/list", get(recurring::list));
+    let recurring_item_router = Router::new()
+        .route("/preview", get(recurring::preview))
+        .route("/skip", post(recurring::skip))
+        .route("/stop", post(recurring::stop))
+        .route("/delete", delete(recurring::delete));
+
+    let installments_router = Router::new().route("/list", get(installments::list));
+    let installment_router = Router::new().route("/delete", delete(installments::delete));
+
+    let import_router = Router::new()
+        .route("/csv/preview", post(imports::csv_preview))
+        .route("/csv/commit", post(imports::csv_commit))
+        .route("/ofx/preview", post(imports::ofx_preview))
+        .route("/ofx/commit", post(imports::ofx_commit))
+        .route("/qif/preview", post(imports::qif_preview))
+        .route("/qif/commit", post(imports::qif_commit))
+        .layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT));
+
+    let duplicates_router = Router::new()
+        .route("/", get(duplicates::list))
+        .route("/merge", post(duplicates::merge))
+        .route("/dismiss", post(duplicates::dismiss));
+
+    let payees_router = Router::new()
+        .route("/list", get(payees::list))
+        .route("/search", get(payees::search))
+        .route("/create", post(payees::create));
+    let payee_router = Router::new()
+        .route("/edit", patch(payees::edit))
+        .route("/delete", delete(payees::delete));
+
+    let rules_router = Router::new()
+        .route("/list", get(rules::list))
+        .route("/create", post(rules::create))
+        .route("/apply", post(rules::apply));
+    let rule_router = Router::new()
+        .route("/edit", patch(rules::edit))
+        .route("/delete", delete(rules::delete));
+
+    let tags_router = Router::new()
+        .route("/list", get(tags::list))
+        .route("/create", post(tags::create));
+    let tag_router = Router::new()
+        .route("/edit", patch(tags::edit))
+        .route("/delete", delete(tags::delete));
+
+    let templates_router = Router::new()
+        .route("/list", get(templates::list))
+        .route("/create", post(templates::create))
+        .route("/suggestions", get(templates::suggestions));
+    let template_router = Router::new()
+        .route("/edit", patch(templates::edit))
+        .route("/delete", delete(templates::delete))
+        .route("/use", post(templates::use_template));
 
     let transactions_router = Router::new()
         .route("/create", post(transactions::create))
         .route("/list", get(transactions::list));
+    let attachments_router = Router::new()
+        .route(
+            "/create",
+            post(attachments::create).layer(DefaultBodyLimit::max(
+                config().attachments.max_size + MULTIPART_OVERHEAD,
+            )),
+        )
+        .route("/list", get(attachments::list));
+    let comments_router = Router::new()
+        .route("/list", get(comments::list))
+        .route("/create", post(comments::create));
+    let comment_router = Router::new()
+        .route("/edit", patch(comments::edit))
+        .route("/delete", delete(comments::delete));
+    let attachment_router = Router::new()
+        .route("/download", get(attachments::download))
+        .route("/delete", delete(attachments::delete));
+
     let transaction_router = Router::new()
         .route("/edit", patch(transactions::edit))
-        .route("/delete", delete(transactions::delete));
+        .route("/delete", delete(transactions::delete))
+        .route("/restore", post(transactions::restore))
+        .route("/reimbursable", patch(refunds::set_reimbursable))
+        .route("/refunds/create", post(refunds::create))
+        .route("/refunds/list", get(refunds::list))
+        .route("/history", get(revisions::list))
+        .route("/history/{id}/revert", post(revisions::revert))
+        .nest(
+            "/attachments",
+            attachments_router.nest("/{id}", attachment_router),
+        )
+        .nest("/comments", comments_router.nest("/{id}", comment_router));
 
     Router::new()
         .route("/list-overview", get(handlers::get_expenses_overview))
+        .route("/list-payees-overview", get(handlers::get_payees_overview))
+        .route("/trash", get(trash::list))
+        .route("/upcoming", get(upcoming::list))
+        .route("/quick-add", post(quick_add::quick_add))
+        .route("/reimbursements/pending", get(refunds::pending))
+        .route("/search", get(search::search))
+        .route("/geo", get(geo::map))
         .nest(
             "/categories",
             categories_router.nest(
                 "/{id}",
-                category_router.nest(
-                    "/transactions",
-                    transactions_router.nest("/{id}", transaction_router),
-                ),
+                category_router
+                    .nest("/recurring", category_recurring_router)
+                    .nest("/installments", category_installments_router)
+                    .nest(
+                        "/transactions",
+                        transactions_router.nest("/{id}", transaction_router),
+                    ),
             ),
         )
+        .nest(
+            "/recurring",
+            recurring_router.nest("/{id}", recurring_item_router),
+        )
+        .nest(
+            "/installments",
+            installments_router.nest("/{id}", installment_router),
+        )
+        .nest("/duplicates", duplicates_router)
+        .nest("/import", import_router)
+        .route("/transactions/bulk", post(bulk::bulk))
+        .nest("/payees", payees_router.nest("/{id}", payee_router))
+        .nest("/rules", rules_router.nest("/{id}", rule_router))
+        .nest("/tags", tags_router.nest("/{id}", tag_router))
+        .nest(
+            "/templates",
+            templates_router.nest("/{id}", template_router),
+        )
         .layer(middleware::from_fn_with_state(state, require_auth))
 }
@@ -0,0 +1,168 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+use surrealdb::RecordId;
+
+use crate::{
+    api::{ApiError, ApiState, auth::extractor::AuthUser},
+    db::{DbError, repo::PayeeRepo},
+    models::Payee,
+};
+
+const MAX_SEARCH_RESULTS: usize = 50;
+
+#[derive(Deserialize)]
+pub struct ItemPayload {
+    name: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    q: String,
+    #[serde(default = "default_search_limit")]
+    limit: usize,
+}
+
+fn default_search_limit() -> usize {
+    10
+}
+
+fn clean_aliases(aliases: Vec<String>) -> Vec<String> {
+    let mut cleaned = Vec::<String>::with_capacity(aliases.len());
+
+    for alias in aliases.into_iter().map(|alias| alias.trim().to_string()) {
+        if !alias.is_empty() && !cleaned.iter().any(|a| a.eq_ignore_ascii_case(&alias)) {
+            cleaned.push(alias);
+        }
+    }
+
+    cleaned
+}
+
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = PayeeRepo::new(&state.db);
+
+    let payees = repo.list(auth.user_id).await?;
+
+    Ok(Json(payees))
+}
+
+pub async fn search(
+    State(state): State<Arc<ApiState>>,
+    Query(query): Query<SearchQuery>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = PayeeRepo::new(&state.db);
+
+    let payees = repo
+        .search(
+            auth.user_id,
+            query.q.trim().to_string(),
+            query.limit.min(MAX_SEARCH_RESULTS),
+        )
+        .await?;
+
+    Ok(Json(payees))
+}
+
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = PayeeRepo::new(&state.db);
+
+    let user_id = auth.user_id;
+    let name = payload.name;
+    let aliases = clean_aliases(payload.aliases);
+
+    if repo.exists(user_id.clone(), name.clone()).await? {
+        return Err(ApiError::AlreadyExists(json!(
+            {"name": "Payee with this name already exists"}
+        )));
+    }
+
+    let payee_id = repo.create(user_id, name.clone(), aliases.clone()).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Payee {
+            id: payee_id,
+            name,
+            aliases,
+        }),
+    ))
+}
+
+pub async fn edit(
+    State(state): State<Arc<ApiState>>,
+    Path(payee_id): Path<String>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = PayeeRepo::new(&state.db);
+
+    let user_id = auth.user_id;
+    let payee_id = RecordId::from_table_key("payee", payee_id);
+    let name = payload.name;
+    let aliases = clean_aliases(payload.aliases);
+
+    if !(repo.user_owns(user_id.clone(), payee_id.clone()).await?) {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this payee".into(),
+        )));
+    }
+
+    if repo
+        .exists_excluding(user_id, name.clone(), payee_id.clone())
+        .await?
+    {
+        return Err(ApiError::AlreadyExists(json!(
+            {"name": "Payee with this name already exists"}
+        )));
+    }
+
+    repo.edit(payee_id.clone(), name.clone(), aliases.clone())
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(Payee {
+            id: payee_id,
+            name,
+            aliases,
+        }),
+    ))
+}
+
+pub async fn delete(
+    State(state): State<Arc<ApiState>>,
+    Path(payee_id): Path<String>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = PayeeRepo::new(&state.db);
+
+    let payee_id = RecordId::from_table_key("payee", payee_id);
+
+    if !(repo.user_owns(auth.user_id, payee_id.clone()).await?) {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this payee".into(),
+        )));
+    }
+
+    repo.delete(payee_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use surrealdb::{Datetime, RecordId};
+
+use crate::{
+    api::{
+        ApiError, ApiState,
+        auth::extractor::AuthUser,
+        defs::TimezoneQuery,
+        expenses::transactions::{DuplicateCheck, check_duplicates},
+    },
+    db::{
+        DbError,
+        repo::{CategoryRepo, RuleRepo, TagRepo, transaction_repo::TransactionRepo},
+    },
+    models::{CategoryName, Transaction, TransactionDraft, serialize_option_record_id},
+    quick_add,
+    rules::{RuleSet, Subject},
+};
+
+#[derive(Deserialize)]
+pub struct QuickAddPayload {
+    text: String,
+    /// Overrides the guessed category.
+    category: Option<String>,
+    #[serde(default)]
+    commit: bool,
+}
+
+#[derive(Serialize)]
+pub struct QuickTag {
+    #[serde(serialize_with = "serialize_option_record_id")]
+    id: Option<RecordId>,
+    name: String,
+}
+
+/// The parsed entry, returned for confirmation when not committing.
+#[derive(Serialize)]
+pub struct QuickDraft {
+    amount: f64,
+    date: Datetime,
+    note: Option<String>,
+    category: Option<CategoryName>,
+    tags: Vec<QuickTag>,
+}
+
+/// Parses a one-line entry like `12.50 coffee yesterday #work`. The category
+/// is the one named in the text, else the first matching rule's, and matching
+/// rules add their tags. With `commit` the transaction is created right away,
+/// along with any tags that don't exist yet, after the same duplicate check
+/// as a regular create; otherwise the parsed draft is returned for
+/// confirmation.
+pub async fn quick_add(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    Query(tz): Query<TimezoneQuery>,
+    Query(check): Query<DuplicateCheck>,
+    Json(payload): Json<QuickAddPayload>,
+) -> Result<Response, ApiError> {
+    let category_repo = CategoryRepo::new(&state.db);
+    let tag_repo = TagRepo::new(&state.db);
+
+    let user_id = auth.user_id;
+
+    let tz = tz.resolve(&state, user_id.clone()).await?;
+
+    let entry = quick_add::parse(&payload.text, Utc::now().with_timezone(&tz))
+        .map_err(|e| ApiError::Validation(json!({ "text": e })))?;
+
+    let categories = category_repo.list_names(user_id.clone()).await?;
+
+    let outcome = RuleSet::new(
+        RuleRepo::new(&state.db)
+            .list_active(user_id.clone())
+            .await?,
+    )
+    .evaluate(&Subject {
+        amount: entry.amount,
+        note: entry.note.as_deref(),
+        payee: None,
+    });
+
+    let category = match payload.category {
+        Some(category_id) => {
+            let category_id = RecordId::from_table_key("category", category_id);
+
+            Some(
+                categories
+                    .iter()
+                    .find(|category| category.id == category_id)
+                    .cloned()
+                    .ok_or(ApiError::Db(DbError::NotFound(
+                        "User does not own this category".into(),
+                    )))?,
+            )
+        }
+        None => match entry
+            .note
+            .as_deref()
+            .and_then(|note| quick_add::guess_category(note, &categories))
+        {
+            Some(category) => Some(category.clone()),
+            None => outcome.category.and_then(|category_id| {
+                categories
+                    .iter()
+                    .find(|category| category.id == category_id)
+                    .cloned()
+            }),
+        },
+    };
+
+    let existing_tags = tag_repo.list(user_id.clone()).await?;
+    let mut tags = entry
+        .tags
+        .into_iter()
+        .map(|name| {
+            match existing_tags
+                .iter()
+                .find(|tag| tag.name.to_lowercase() == name.to_lowercase())
+            {
+                Some(tag) => QuickTag {
+                    id: Some(tag.id.clone()),
+                    name: tag.name.clone(),
+                },
+                None => QuickTag { id: None, name },
+            }
+        })
+        .collect::<Vec<_>>();
+
+    for tag_id in outcome.tags {
+        if tags.iter().any(|tag| tag.id.as_ref() == Some(&tag_id)) {
+            continue;
+        }
+
+        if let Some(tag) = existing_tags.iter().find(|tag| tag.id == tag_id) {
+            tags.push(QuickTag {
+                id: Some(tag.id.clone()),
+                name: tag.name.clone(),
+            });
+        }
+    }
+
+    if !payload.commit {
+        return Ok(Json(QuickDraft {
+            amount: entry.amount,
+            date: entry.date.into(),
+            note: entry.note,
+            category,
+            tags,
+        })
+        .into_response());
+    }
+
+    let Some(category) = category else {
+        return Err(ApiError::Validation(json!(
+            {"category": "No category could be guessed, please pick one"}
+        )));
+    };
+
+    let mut draft = TransactionDraft {
+        account: None,
+        amount: entry.amount,
+        note: entry.note,
+        date: entry.date.into(),
+        splits: Vec::new(),
+        tags: Vec::with_capacity(tags.len()),
+        payee: None,
+    };
+
+    // Checked before any new tags are created, so a rejected entry leaves
+    // nothing behind.
+    if !check.allow_duplicate {
+        check_duplicates(&state, user_id.clone(), &draft).await?;
+    }
+
+    for tag in tags {
+        draft.tags.push(match tag.id {
+            Some(tag_id) => tag_id,
+            None => tag_repo.create(user_id.clone(), tag.name).await?,
+        });
+    }
+
+    let transaction_id = TransactionRepo::new(&state.db)
+        .create(category.id.clone(), draft.clone())
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Transaction {
+            id: transaction_id,
+            amount: draft.amount,
+            note: draft.note,
+            date: draft.date,
+            account: None,
+            splits: Vec::new(),
+            tags: draft.tags,
+            payee: None,
+            category: Some(category.id),
+            refund_of: None,
+            refund_kind: None,
+            installment: None,
+            installment_number: None,
+            status: None,
+            location: None,
+        }),
+    )
+        .into_response())
+}
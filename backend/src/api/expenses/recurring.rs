@@ -0,0 +1,257 @@
+use std::{collections::HashSet, sync::Arc};
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+use surrealdb::{Datetime, RecordId};
+
+use crate::{
+    api::{ApiError, ApiState, auth::extractor::AuthUser, expenses::transactions::owned_account},
+    db::{
+        DbError,
+        repo::{CategoryRepo, RecurringRepo},
+    },
+    models::{Frequency, Occurrence, Recurring, RecurringSchedule},
+    recurrence::{self, to_utc},
+};
+
+const MAX_PREVIEW: usize = 100;
+
+#[derive(Deserialize)]
+pub struct ItemPayload {
+    pub amount: f64,
+    pub note: Option<String>,
+    pub account: Option<String>,
+    pub frequency: Frequency,
+    #[serde(default = "default_interval")]
+    pub interval: u32,
+    pub start: Datetime,
+    pub until: Option<Datetime>,
+    pub count: Option<u32>,
+}
+
+fn default_interval() -> u32 {
+    1
+}
+
+#[derive(Deserialize)]
+pub struct PreviewQuery {
+    #[serde(default = "default_preview_limit")]
+    pub limit: usize,
+}
+
+fn default_preview_limit() -> usize {
+    10
+}
+
+#[derive(Deserialize)]
+pub struct SkipPayload {
+    pub date: Datetime,
+}
+
+async fn owned_recurring(
+    repo: &RecurringRepo<'_>,
+    user_id: RecordId,
+    recurring_id: String,
+) -> Result<RecordId, ApiError> {
+    let recurring_id = RecordId::from_table_key("recurring", recurring_id);
+
+    if !(repo.user_owns(user_id, recurring_id.clone()).await?) {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this recurring transaction".into(),
+        )));
+    }
+
+    Ok(recurring_id)
+}
+
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    Path(category_id): Path<String>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let category_repo = CategoryRepo::new(&state.db);
+    let recurring_repo = RecurringRepo::new(&state.db);
+
+    let category_id = RecordId::from_table_key("category", category_id);
+
+    if payload.interval == 0 {
+        return Err(ApiError::Validation(json!(
+            {"interval": "Interval must be at least 1"}
+        )));
+    }
+
+    if payload.count == Some(0) {
+        return Err(ApiError::Validation(json!(
+            {"count": "Count must be at least 1"}
+        )));
+    }
+
+    if payload
+        .until
+        .as_ref()
+        .is_some_and(|until| *until < payload.start)
+    {
+        return Err(ApiError::Validation(json!(
+            {"until": "End date must not be before the start date"}
+        )));
+    }
+
+    if !(category_repo
+        .user_owns(auth.user_id.clone(), category_id.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this category".into(),
+        )));
+    }
+
+    let account_id = owned_account(&state, auth.user_id, payload.account).await?;
+
+    let schedule = RecurringSchedule {
+        frequency: payload.frequency,
+        interval: payload.interval,
+        start: payload.start,
+        until: payload.until,
+        count: payload.count,
+    };
+    let next_date = recurrence::occurrence(&schedule, 0).map(Datetime::from);
+
+    let recurring_id = recurring_repo
+        .create(
+            category_id.clone(),
+            account_id.clone(),
+            payload.amount,
+            payload.note.clone(),
+            schedule.clone(),
+            next_date.clone(),
+        )
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Recurring {
+            id: recurring_id,
+            category: category_id,
+            account: account_id,
+            amount: payload.amount,
+            note: payload.note,
+            schedule,
+            occurrences: 0,
+            next_date,
+            stopped: false,
+        }),
+    ))
+}
+
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = RecurringRepo::new(&state.db);
+
+    let recurring = repo.list(auth.user_id).await?;
+
+    Ok(Json(recurring))
+}
+
+pub async fn preview(
+    State(state): State<Arc<ApiState>>,
+    Path(recurring_id): Path<String>,
+    Query(query): Query<PreviewQuery>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = RecurringRepo::new(&state.db);
+
+    let recurring_id = owned_recurring(&repo, auth.user_id, recurring_id).await?;
+    let recurring = repo.get(recurring_id.clone()).await?;
+
+    if recurring.stopped {
+        return Ok(Json(Vec::new()));
+    }
+
+    let skipped = repo
+        .skipped_dates(recurring_id)
+        .await?
+        .iter()
+        .map(to_utc)
+        .collect::<HashSet<_>>();
+
+    let occurrences = (recurring.occurrences..)
+        .map_while(|n| recurrence::occurrence(&recurring.schedule, n))
+        .take(query.limit.min(MAX_PREVIEW))
+        .map(|date| Occurrence {
+            skipped: skipped.contains(&date),
+            date: date.into(),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Json(occurrences))
+}
+
+pub async fn skip(
+    State(state): State<Arc<ApiState>>,
+    Path(recurring_id): Path<String>,
+    auth: AuthUser,
+    Json(payload): Json<SkipPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = RecurringRepo::new(&state.db);
+
+    let recurring_id = owned_recurring(&repo, auth.user_id, recurring_id).await?;
+    let recurring = repo.get(recurring_id.clone()).await?;
+
+    let date = to_utc(&payload.date);
+    let scheduled = !recurring.stopped
+        && (recurring.occurrences..)
+            .map_while(|n| recurrence::occurrence(&recurring.schedule, n))
+            .find(|occurrence| *occurrence >= date)
+            .is_some_and(|occurrence| occurrence == date);
+
+    if !scheduled {
+        return Err(ApiError::Validation(json!(
+            {"date": "Date is not an upcoming occurrence of this schedule"}
+        )));
+    }
+
+    if !(repo.skip(recurring_id, payload.date).await?) {
+        return Err(ApiError::AlreadyExists(json!(
+            {"date": "This occurrence has already been created or skipped"}
+        )));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn stop(
+    State(state): State<Arc<ApiState>>,
+    Path(recurring_id): Path<String>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = RecurringRepo::new(&state.db);
+
+    let recurring_id = owned_recurring(&repo, auth.user_id, recurring_id).await?;
+
+    repo.stop(recurring_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn delete(
+    State(state): State<Arc<ApiState>>,
+    Path(recurring_id): Path<String>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = RecurringRepo::new(&state.db);
+
+    let recurring_id = owned_recurring(&repo, auth.user_id, recurring_id).await?;
+
+    repo.delete(recurring_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,237 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+use surrealdb::{Datetime, RecordId};
+
+use crate::{
+    api::{
+        ApiError, ApiState,
+        auth::extractor::AuthUser,
+        expenses::transactions::{owned_account, owned_transaction, transaction},
+    },
+    db::repo::transaction_repo::{REFUND_TOLERANCE, TransactionRepo},
+    models::{RefundKind, Refundable, Refunds, Split, Transaction, TransactionDraft},
+};
+
+#[derive(Deserialize)]
+pub struct ItemPayload {
+    kind: RefundKind,
+    amount: f64,
+    note: Option<String>,
+    date: Datetime,
+    account: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ReimbursablePayload {
+    reimbursable: bool,
+}
+
+/// Shares `amount` out over the original's splits in proportion, so that the
+/// refund nets against the same categories. The last split takes whatever
+/// rounding left over.
+fn refund_splits(original: &Refundable, amount: f64) -> Vec<Split> {
+    let mut remaining = amount;
+
+    original
+        .splits
+        .iter()
+        .enumerate()
+        .map(|(i, split)| {
+            let share = if i + 1 == original.splits.len() {
+                remaining
+            } else {
+                (split.amount * amount / original.amount * 100.0).round() / 100.0
+            };
+
+            remaining -= share;
+
+            Split {
+                category: split.category.clone(),
+                amount: share,
+            }
+        })
+        .collect()
+}
+
+fn exceeds_original() -> ApiError {
+    ApiError::Validation(json!(
+        {"amount": "Refunds cannot add up to more than the original transaction"}
+    ))
+}
+
+/// Keeps the refunds of a transaction within its amount when `transaction_id`,
+/// either the original or one of its refunds, is edited to `amount`.
+pub async fn check_refund_total(
+    state: &ApiState,
+    transaction_id: RecordId,
+    amount: f64,
+) -> Result<(), ApiError> {
+    let repo = TransactionRepo::new(&state.db);
+
+    let edited = repo.refundable(transaction_id.clone(), None).await?;
+
+    let within = match edited.refund_of {
+        Some(original_id) => {
+            let original = repo.refundable(original_id, Some(transaction_id)).await?;
+
+            original.refunded + amount <= original.amount + REFUND_TOLERANCE
+        }
+        None => edited.refunded <= amount + REFUND_TOLERANCE,
+    };
+
+    if !within {
+        return Err(exceeds_original());
+    }
+
+    Ok(())
+}
+
+/// Keeps the refunds of `original_id` within its amount once those of
+/// `duplicate_id` are merged into it.
+pub async fn check_merged_refunds(
+    state: &ApiState,
+    original_id: RecordId,
+    duplicate_id: RecordId,
+) -> Result<(), ApiError> {
+    let repo = TransactionRepo::new(&state.db);
+
+    let original = repo
+        .refundable(original_id.clone(), Some(duplicate_id.clone()))
+        .await?;
+    let duplicate = repo.refundable(duplicate_id, Some(original_id)).await?;
+
+    if original.refunded + duplicate.refunded > original.amount + REFUND_TOLERANCE {
+        return Err(exceeds_original());
+    }
+
+    Ok(())
+}
+
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    Path((_, transaction_id)): Path<(String, String)>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = TransactionRepo::new(&state.db);
+
+    let original_id = owned_transaction(&state, auth.user_id.clone(), transaction_id).await?;
+
+    let original = repo.refundable(original_id.clone(), None).await?;
+
+    if original.refund_of.is_some() {
+        return Err(ApiError::Validation(json!(
+            {"transaction": "A refund cannot be refunded itself"}
+        )));
+    }
+
+    if payload.amount <= 0.0 {
+        return Err(ApiError::Validation(json!(
+            {"amount": "Amount must be greater than zero"}
+        )));
+    }
+
+    if original.refunded + payload.amount > original.amount + REFUND_TOLERANCE {
+        return Err(exceeds_original());
+    }
+
+    let account = match payload.account {
+        Some(_) => owned_account(&state, auth.user_id, payload.account).await?,
+        None => original.account.clone(),
+    };
+
+    let draft = TransactionDraft {
+        account,
+        amount: payload.amount,
+        note: payload.note,
+        date: payload.date,
+        splits: refund_splits(&original, payload.amount),
+        tags: original.tags.clone(),
+        payee: original.payee.clone(),
+    };
+
+    let refund_id = repo
+        .create_refund(
+            original.category,
+            original_id.clone(),
+            payload.kind,
+            draft.clone(),
+        )
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Transaction {
+            refund_of: Some(original_id),
+            refund_kind: Some(payload.kind),
+            ..transaction(refund_id, draft)
+        }),
+    ))
+}
+
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    Path((_, transaction_id)): Path<(String, String)>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = TransactionRepo::new(&state.db);
+
+    let transaction_id = owned_transaction(&state, auth.user_id, transaction_id).await?;
+
+    let original = repo.refundable(transaction_id.clone(), None).await?;
+
+    Ok(Json(Refunds {
+        amount: original.amount,
+        refunded: original.refunded,
+        remaining: (original.amount - original.refunded).max(0.0),
+        refunds: repo.refunds(transaction_id).await?,
+    }))
+}
+
+/// Marks an expense as one that is expected to be paid back, which puts it on
+/// the pending reimbursements report until it is.
+pub async fn set_reimbursable(
+    State(state): State<Arc<ApiState>>,
+    Path((_, transaction_id)): Path<(String, String)>,
+    auth: AuthUser,
+    Json(payload): Json<ReimbursablePayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = TransactionRepo::new(&state.db);
+
+    let transaction_id = owned_transaction(&state, auth.user_id, transaction_id).await?;
+
+    if repo
+        .refundable(transaction_id.clone(), None)
+        .await?
+        .refund_of
+        .is_some()
+    {
+        return Err(ApiError::Validation(json!(
+            {"transaction": "A refund cannot be reimbursable"}
+        )));
+    }
+
+    repo.set_reimbursable(transaction_id, payload.reimbursable)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn pending(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let pending = TransactionRepo::new(&state.db)
+        .pending_reimbursements(auth.user_id)
+        .await?;
+
+    Ok(Json(pending))
+}
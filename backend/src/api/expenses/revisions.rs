@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use surrealdb::RecordId;
+
+use crate::{
+    api::{
+        ApiError, ApiState,
+        auth::extractor::AuthUser,
+        expenses::{
+            refunds::check_refund_total,
+            transactions::{self, ItemPayload, SplitPayload, owned_transaction},
+        },
+    },
+    db::repo::{RevisionRepo, transaction_repo::TransactionRepo},
+    models::TransactionSnapshot,
+};
+
+/// Turns a snapshot back into a payload so that reverting goes through the
+/// same ownership and split checks as an edit.
+fn payload(snapshot: TransactionSnapshot) -> ItemPayload {
+    let key = |id: RecordId| id.key().to_string();
+
+    ItemPayload {
+        status: None,
+        location: None,
+        amount: snapshot.amount,
+        note: snapshot.note,
+        date: snapshot.date,
+        account: snapshot.account.map(key),
+        splits: snapshot
+            .splits
+            .into_iter()
+            .map(|split| SplitPayload {
+                category: key(split.category),
+                amount: split.amount,
+            })
+            .collect(),
+        tags: snapshot.tags.into_iter().map(key).collect(),
+        payee: snapshot.payee.map(key),
+    }
+}
+
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    Path((_, transaction_id)): Path<(String, String)>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let transaction_id = owned_transaction(&state, auth.user_id, transaction_id).await?;
+
+    let revisions = RevisionRepo::new(&state.db).list(transaction_id).await?;
+
+    Ok(Json(revisions))
+}
+
+/// Undoes a revision, restoring the values it replaced. The revert is itself
+/// recorded as a new revision.
+pub async fn revert(
+    State(state): State<Arc<ApiState>>,
+    Path((_, transaction_id, revision_id)): Path<(String, String, String)>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let transaction_id = owned_transaction(&state, auth.user_id.clone(), transaction_id).await?;
+    let revision_id = RecordId::from_table_key("transaction_revision", revision_id);
+
+    transactions::ensure_unlocked(&state, transaction_id.clone()).await?;
+
+    let revision = RevisionRepo::new(&state.db)
+        .get(transaction_id.clone(), revision_id)
+        .await?;
+
+    let draft =
+        transactions::owned_draft(&state, auth.user_id.clone(), payload(revision.before)).await?;
+
+    check_refund_total(&state, transaction_id.clone(), draft.amount).await?;
+
+    TransactionRepo::new(&state.db)
+        .edit(
+            transaction_id.clone(),
+            auth.user_id,
+            draft.clone(),
+            None,
+            None,
+        )
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(transactions::transaction(transaction_id, draft)),
+    ))
+}
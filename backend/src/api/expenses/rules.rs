@@ -0,0 +1,342 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use surrealdb::{Datetime, RecordId};
+
+use crate::{
+    api::{
+        ApiError, ApiState,
+        auth::extractor::AuthUser,
+        expenses::transactions::{owned_payee, owned_tags},
+    },
+    db::{
+        DbError,
+        repo::{CategoryRepo, RuleRepo, transaction_repo::TransactionRepo},
+    },
+    models::{
+        BulkAction, Rule, RuleConditions, serialize_option_record_id, serialize_record_id,
+        serialize_record_ids,
+    },
+    rules::{self, RuleSet, Subject},
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConditionsPayload {
+    note_contains: Option<String>,
+    note_regex: Option<String>,
+    min_amount: Option<f64>,
+    max_amount: Option<f64>,
+    payee: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ItemPayload {
+    name: String,
+    #[serde(default)]
+    priority: i64,
+    conditions: ConditionsPayload,
+    category: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyPayload {
+    #[serde(default = "default_dry_run")]
+    dry_run: bool,
+    start: Option<Datetime>,
+    end: Option<Datetime>,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleChange {
+    #[serde(serialize_with = "serialize_record_id")]
+    transaction: RecordId,
+    amount: f64,
+    note: Option<String>,
+    #[serde(serialize_with = "serialize_record_id")]
+    from_category: RecordId,
+    #[serde(serialize_with = "serialize_option_record_id")]
+    to_category: Option<RecordId>,
+    #[serde(serialize_with = "serialize_record_ids")]
+    add_tags: Vec<RecordId>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Applied {
+    dry_run: bool,
+    changes: Vec<RuleChange>,
+}
+
+struct RuleDraft {
+    name: String,
+    priority: i64,
+    conditions: RuleConditions,
+    category: Option<RecordId>,
+    tags: Vec<RecordId>,
+}
+
+impl RuleDraft {
+    fn into_rule(self, id: RecordId) -> Rule {
+        Rule {
+            id,
+            name: self.name,
+            priority: self.priority,
+            conditions: self.conditions,
+            category: self.category,
+            tags: self.tags,
+        }
+    }
+}
+
+/// Validates a rule payload and resolves its ids against the user's records.
+async fn owned_rule(
+    state: &ApiState,
+    user_id: RecordId,
+    payload: ItemPayload,
+) -> Result<RuleDraft, ApiError> {
+    let name = payload.name.trim().to_string();
+    let conditions = payload.conditions;
+
+    if name.is_empty() {
+        return Err(ApiError::Validation(json!({"name": "Name is required"})));
+    }
+
+    let note_contains = conditions.note_contains.filter(|note| !note.is_empty());
+    let note_regex = conditions.note_regex.filter(|regex| !regex.is_empty());
+
+    if note_contains.is_none()
+        && note_regex.is_none()
+        && conditions.min_amount.is_none()
+        && conditions.max_amount.is_none()
+        && conditions.payee.is_none()
+    {
+        return Err(ApiError::Validation(json!(
+            {"conditions": "A rule needs at least one condition"}
+        )));
+    }
+
+    if let Some(Err(e)) = note_regex.as_deref().map(rules::compile) {
+        return Err(ApiError::Validation(json!({"noteRegex": e.to_string()})));
+    }
+
+    if let (Some(min), Some(max)) = (conditions.min_amount, conditions.max_amount)
+        && min > max
+    {
+        return Err(ApiError::Validation(json!(
+            {"maxAmount": "Maximum amount must not be below the minimum"}
+        )));
+    }
+
+    if payload.category.is_none() && payload.tags.is_empty() {
+        return Err(ApiError::Validation(json!(
+            {"category": "A rule needs a category or at least one tag"}
+        )));
+    }
+
+    let category = match payload.category {
+        Some(category_id) => {
+            let category_id = RecordId::from_table_key("category", category_id);
+
+            if !(CategoryRepo::new(&state.db)
+                .user_owns(user_id.clone(), category_id.clone())
+                .await?)
+            {
+                return Err(ApiError::Db(DbError::NotFound(
+                    "User does not own this category".into(),
+                )));
+            }
+
+            Some(category_id)
+        }
+        None => None,
+    };
+
+    Ok(RuleDraft {
+        name,
+        priority: payload.priority,
+        conditions: RuleConditions {
+            note_contains,
+            note_regex,
+            min_amount: conditions.min_amount,
+            max_amount: conditions.max_amount,
+            payee: owned_payee(state, user_id.clone(), conditions.payee).await?,
+        },
+        category,
+        tags: owned_tags(state, user_id, payload.tags).await?,
+    })
+}
+
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let rules = RuleRepo::new(&state.db).list(auth.user_id).await?;
+
+    Ok(Json(rules))
+}
+
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let rule = owned_rule(&state, auth.user_id.clone(), payload).await?;
+
+    let rule_id = RuleRepo::new(&state.db)
+        .create(
+            auth.user_id,
+            rule.name.clone(),
+            rule.priority,
+            rule.conditions.clone(),
+            rule.category.clone(),
+            rule.tags.clone(),
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(rule.into_rule(rule_id))))
+}
+
+pub async fn edit(
+    State(state): State<Arc<ApiState>>,
+    Path(rule_id): Path<String>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = RuleRepo::new(&state.db);
+
+    let rule_id = RecordId::from_table_key("rule", rule_id);
+
+    if !(repo
+        .user_owns(auth.user_id.clone(), rule_id.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this rule".into(),
+        )));
+    }
+
+    let rule = owned_rule(&state, auth.user_id, payload).await?;
+
+    repo.edit(
+        rule_id.clone(),
+        rule.name.clone(),
+        rule.priority,
+        rule.conditions.clone(),
+        rule.category.clone(),
+        rule.tags.clone(),
+    )
+    .await?;
+
+    Ok((StatusCode::OK, Json(rule.into_rule(rule_id))))
+}
+
+pub async fn delete(
+    State(state): State<Arc<ApiState>>,
+    Path(rule_id): Path<String>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = RuleRepo::new(&state.db);
+
+    let rule_id = RecordId::from_table_key("rule", rule_id);
+
+    if !(repo.user_owns(auth.user_id, rule_id.clone()).await?) {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this rule".into(),
+        )));
+    }
+
+    repo.delete(rule_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Runs the user's rules over their existing transactions. With `dryRun` (the
+/// default) only the would-be changes are returned; otherwise they are also
+/// written through `TransactionRepo::bulk`. Split transactions keep their
+/// category but can still gain tags; reconciled transactions are left alone.
+pub async fn apply(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    Json(payload): Json<ApplyPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let transaction_repo = TransactionRepo::new(&state.db);
+
+    let rules = RuleSet::new(
+        RuleRepo::new(&state.db)
+            .list_active(auth.user_id.clone())
+            .await?,
+    );
+
+    let targets = transaction_repo
+        .rule_targets(auth.user_id.clone(), payload.start, payload.end)
+        .await?;
+
+    let mut changes = Vec::new();
+    let mut operations = Vec::new();
+
+    for target in targets {
+        let outcome = rules.evaluate(&Subject {
+            amount: target.amount,
+            note: target.note.as_deref(),
+            payee: target.payee.as_ref(),
+        });
+
+        let to_category = outcome
+            .category
+            .filter(|category| !target.split && *category != target.category);
+        let add_tags = outcome
+            .tags
+            .into_iter()
+            .filter(|tag| !target.tags.contains(tag))
+            .collect::<Vec<_>>();
+
+        if to_category.is_none() && add_tags.is_empty() {
+            continue;
+        }
+
+        if let Some(category) = &to_category {
+            operations.push((
+                target.id.clone(),
+                BulkAction::MoveCategory(category.clone()),
+            ));
+        }
+
+        for tag in &add_tags {
+            operations.push((target.id.clone(), BulkAction::AddTag(tag.clone())));
+        }
+
+        changes.push(RuleChange {
+            transaction: target.id,
+            amount: target.amount,
+            note: target.note,
+            from_category: target.category,
+            to_category,
+            add_tags,
+        });
+    }
+
+    if !payload.dry_run && !operations.is_empty() {
+        transaction_repo.bulk(auth.user_id, operations).await?;
+    }
+
+    Ok(Json(Applied {
+        dry_run: payload.dry_run,
+        changes,
+    }))
+}
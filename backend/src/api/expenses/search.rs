@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    api::{ApiError, ApiState, auth::extractor::AuthUser},
+    db::repo::SearchRepo,
+};
+
+const MAX_SEARCH_RESULTS: usize = 50;
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    q: String,
+    #[serde(default = "default_search_limit")]
+    limit: usize,
+}
+
+fn default_search_limit() -> usize {
+    10
+}
+
+pub async fn search(
+    State(state): State<Arc<ApiState>>,
+    Query(query): Query<SearchQuery>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let q = query.q.trim().to_string();
+
+    if q.is_empty() {
+        return Err(ApiError::Validation(
+            json!({"q": "Search text is required"}),
+        ));
+    }
+
+    let results = SearchRepo::new(&state.db)
+        .search(auth.user_id, q, query.limit.min(MAX_SEARCH_RESULTS))
+        .await?;
+
+    Ok(Json(results))
+}
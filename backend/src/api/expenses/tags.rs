@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+use surrealdb::RecordId;
+
+use crate::{
+    api::{ApiError, ApiState, auth::extractor::AuthUser},
+    db::{DbError, repo::TagRepo},
+    models::Tag,
+};
+
+#[derive(Deserialize)]
+pub struct ItemPayload {
+    name: String,
+}
+
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = TagRepo::new(&state.db);
+
+    let tags = repo.list(auth.user_id).await?;
+
+    Ok(Json(tags))
+}
+
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = TagRepo::new(&state.db);
+
+    let user_id = auth.user_id;
+    let name = payload.name;
+
+    if repo.exists(user_id.clone(), name.clone()).await? {
+        return Err(ApiError::AlreadyExists(json!(
+            {"name": "Tag with this name already exists"}
+        )));
+    }
+
+    let tag_id = repo.create(user_id, name.clone()).await?;
+
+    Ok((StatusCode::CREATED, Json(Tag { id: tag_id, name })))
+}
+
+pub async fn edit(
+    State(state): State<Arc<ApiState>>,
+    Path(tag_id): Path<String>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = TagRepo::new(&state.db);
+
+    let user_id = auth.user_id;
+    let tag_id = RecordId::from_table_key("tag", tag_id);
+    let name = payload.name;
+
+    if !(repo.user_owns(user_id.clone(), tag_id.clone()).await?) {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this tag".into(),
+        )));
+    }
+
+    if repo
+        .exists_excluding(user_id, name.clone(), tag_id.clone())
+        .await?
+    {
+        return Err(ApiError::AlreadyExists(json!(
+            {"name": "Tag with this name already exists"}
+        )));
+    }
+
+    repo.edit(tag_id.clone(), name.clone()).await?;
+
+    Ok((StatusCode::OK, Json(Tag { id: tag_id, name })))
+}
+
+pub async fn delete(
+    State(state): State<Arc<ApiState>>,
+    Path(tag_id): Path<String>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = TagRepo::new(&state.db);
+
+    let tag_id = RecordId::from_table_key("tag", tag_id);
+
+    if !(repo.user_owns(auth.user_id, tag_id.clone()).await?) {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this tag".into(),
+        )));
+    }
+
+    repo.delete(tag_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
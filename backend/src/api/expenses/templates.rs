@@ -0,0 +1,286 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use surrealdb::RecordId;
+
+use crate::{
+    api::{
+        ApiError, ApiState,
+        auth::extractor::AuthUser,
+        expenses::transactions::{DuplicateCheck, check_duplicates, owned_tags, transaction},
+    },
+    db::{
+        DbError,
+        repo::{CategoryRepo, RuleRepo, TemplateRepo, transaction_repo::TransactionRepo},
+    },
+    models::{Template, TransactionDraft},
+    rules::{RuleSet, Subject},
+};
+
+const MAX_SUGGESTIONS: usize = 50;
+
+/// How far back suggestions look for repeated entries.
+const SUGGESTION_DAYS: i64 = 90;
+
+#[derive(Deserialize)]
+pub struct ItemPayload {
+    name: String,
+    category: String,
+    amount: f64,
+    note: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SuggestionsQuery {
+    #[serde(default = "default_suggestions")]
+    limit: usize,
+}
+
+fn default_suggestions() -> usize {
+    10
+}
+
+async fn owned_template(
+    repo: &TemplateRepo<'_>,
+    user_id: RecordId,
+    template_id: String,
+) -> Result<RecordId, ApiError> {
+    let template_id = RecordId::from_table_key("template", template_id);
+
+    if !(repo.user_owns(user_id, template_id.clone()).await?) {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this template".into(),
+        )));
+    }
+
+    Ok(template_id)
+}
+
+async fn owned_category(
+    state: &ApiState,
+    user_id: RecordId,
+    category_id: String,
+) -> Result<RecordId, ApiError> {
+    let category_id = RecordId::from_table_key("category", category_id);
+
+    if !(CategoryRepo::new(&state.db)
+        .user_owns(user_id, category_id.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this category".into(),
+        )));
+    }
+
+    Ok(category_id)
+}
+
+fn validate(payload: &ItemPayload) -> Result<String, ApiError> {
+    let name = payload.name.trim().to_string();
+
+    if name.is_empty() {
+        return Err(ApiError::Validation(json!({"name": "Name is required"})));
+    }
+
+    if payload.amount <= 0.0 {
+        return Err(ApiError::Validation(json!(
+            {"amount": "Amount must be greater than zero"}
+        )));
+    }
+
+    Ok(name)
+}
+
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let templates = TemplateRepo::new(&state.db).list(auth.user_id).await?;
+
+    Ok(Json(templates))
+}
+
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = TemplateRepo::new(&state.db);
+
+    let name = validate(&payload)?;
+
+    if repo.exists(auth.user_id.clone(), name.clone()).await? {
+        return Err(ApiError::AlreadyExists(json!(
+            {"name": "Template with this name already exists"}
+        )));
+    }
+
+    let category_id = owned_category(&state, auth.user_id.clone(), payload.category).await?;
+    let tag_ids = owned_tags(&state, auth.user_id.clone(), payload.tags).await?;
+
+    let template_id = repo
+        .create(
+            auth.user_id,
+            name.clone(),
+            category_id.clone(),
+            payload.amount,
+            payload.note.clone(),
+            tag_ids.clone(),
+        )
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Template {
+            id: template_id,
+            name,
+            category: category_id,
+            amount: payload.amount,
+            note: payload.note,
+            tags: tag_ids,
+            uses: 0,
+        }),
+    ))
+}
+
+pub async fn edit(
+    State(state): State<Arc<ApiState>>,
+    Path(template_id): Path<String>,
+    auth: AuthUser,
+    Json(payload): Json<ItemPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = TemplateRepo::new(&state.db);
+
+    let template_id = owned_template(&repo, auth.user_id.clone(), template_id).await?;
+    let name = validate(&payload)?;
+
+    if repo
+        .exists_excluding(auth.user_id.clone(), name.clone(), template_id.clone())
+        .await?
+    {
+        return Err(ApiError::AlreadyExists(json!(
+            {"name": "Template with this name already exists"}
+        )));
+    }
+
+    let category_id = owned_category(&state, auth.user_id.clone(), payload.category).await?;
+    let tag_ids = owned_tags(&state, auth.user_id, payload.tags).await?;
+
+    repo.edit(
+        template_id.clone(),
+        name,
+        category_id,
+        payload.amount,
+        payload.note,
+        tag_ids,
+    )
+    .await?;
+
+    Ok(Json(repo.get(template_id).await?))
+}
+
+pub async fn delete(
+    State(state): State<Arc<ApiState>>,
+    Path(template_id): Path<String>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = TemplateRepo::new(&state.db);
+
+    let template_id = owned_template(&repo, auth.user_id, template_id).await?;
+
+    repo.delete(template_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Logs the template as a transaction dated now. Matching rules add their tags,
+/// and the entry goes through the same duplicate check as a regular create.
+pub async fn use_template(
+    State(state): State<Arc<ApiState>>,
+    Path(template_id): Path<String>,
+    Query(check): Query<DuplicateCheck>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = TemplateRepo::new(&state.db);
+
+    let template_id = owned_template(&repo, auth.user_id.clone(), template_id).await?;
+    let template = repo.get(template_id.clone()).await?;
+
+    if !(CategoryRepo::new(&state.db)
+        .user_owns(auth.user_id.clone(), template.category.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this category".into(),
+        )));
+    }
+
+    let mut draft = TransactionDraft {
+        account: None,
+        amount: template.amount,
+        note: template.note,
+        date: Utc::now().into(),
+        splits: Vec::new(),
+        tags: template.tags,
+        payee: None,
+    };
+
+    // The template names its category, so rules only add tags.
+    let outcome = RuleSet::new(
+        RuleRepo::new(&state.db)
+            .list_active(auth.user_id.clone())
+            .await?,
+    )
+    .evaluate(&Subject {
+        amount: draft.amount,
+        note: draft.note.as_deref(),
+        payee: None,
+    });
+
+    for tag in outcome.tags {
+        if !draft.tags.contains(&tag) {
+            draft.tags.push(tag);
+        }
+    }
+
+    if !check.allow_duplicate {
+        check_duplicates(&state, auth.user_id, &draft).await?;
+    }
+
+    let transaction_id = TransactionRepo::new(&state.db)
+        .create(template.category, draft.clone())
+        .await?;
+
+    repo.record_use(template_id).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(transaction(transaction_id, draft)),
+    ))
+}
+
+/// Suggests templates from the entries the user has logged repeatedly over
+/// the last few months.
+pub async fn suggestions(
+    State(state): State<Arc<ApiState>>,
+    Query(query): Query<SuggestionsQuery>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let since = Utc::now() - Duration::days(SUGGESTION_DAYS);
+
+    let entries = TransactionRepo::new(&state.db)
+        .frequent(auth.user_id, since.into(), query.limit.min(MAX_SUGGESTIONS))
+        .await?;
+
+    Ok(Json(entries))
+}
@@ -6,28 +6,326 @@ use axum::{
     http::StatusCode,
     response::IntoResponse,
 };
+use chrono::Utc;
 use serde::Deserialize;
+use serde_json::json;
 use surrealdb::{Datetime, RecordId};
 
 use crate::{
-    api::{ApiError, ApiState, auth::extractor::AuthUser, defs::DateRange},
+    api::{
+        ApiError, ApiState,
+        auth::extractor::AuthUser,
+        defs::{DateRange, TagFilter, TimezoneQuery},
+        expenses::refunds::check_refund_total,
+    },
     db::{
         DbError,
-        repo::{CategoryRepo, transaction_repo::TransactionRepo},
+        repo::{
+            AccountRepo, CategoryRepo, PayeeRepo, RuleRepo, TagRepo,
+            transaction_repo::TransactionRepo,
+        },
     },
-    models::Transaction,
+    duplicates,
+    models::{Location, Split, Transaction, TransactionDraft, TransactionStatus},
+    recurrence::to_utc,
+    rules::{RuleSet, Subject},
 };
 
+const SPLIT_TOLERANCE: f64 = 0.005;
+
 #[derive(Deserialize)]
 pub struct ItemPayload {
     pub amount: f64,
     pub note: Option<String>,
     pub date: Datetime,
+    pub account: Option<String>,
+    #[serde(default)]
+    pub splits: Vec<SplitPayload>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub payee: Option<String>,
+    pub status: Option<TransactionStatus>,
+    pub location: Option<Location>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateCheck {
+    #[serde(default)]
+    pub allow_duplicate: bool,
+}
+
+#[derive(Deserialize)]
+pub struct SplitPayload {
+    pub category: String,
+    pub amount: f64,
+}
+
+pub async fn owned_account(
+    state: &ApiState,
+    user_id: RecordId,
+    account_id: Option<String>,
+) -> Result<Option<RecordId>, ApiError> {
+    let Some(account_id) = account_id else {
+        return Ok(None);
+    };
+
+    let account_id = RecordId::from_table_key("account", account_id);
+
+    if !(AccountRepo::new(&state.db)
+        .user_owns(user_id, account_id.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this account".into(),
+        )));
+    }
+
+    Ok(Some(account_id))
+}
+
+pub async fn owned_transaction(
+    state: &ApiState,
+    user_id: RecordId,
+    transaction_id: String,
+) -> Result<RecordId, ApiError> {
+    let transaction_id = RecordId::from_table_key("transaction", transaction_id);
+
+    if !(TransactionRepo::new(&state.db)
+        .user_owns(user_id, transaction_id.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this transaction".into(),
+        )));
+    }
+
+    Ok(transaction_id)
+}
+
+/// Rejects changes to a transaction locked by a finished reconciliation.
+pub async fn ensure_unlocked(state: &ApiState, transaction_id: RecordId) -> Result<(), ApiError> {
+    if !TransactionRepo::new(&state.db)
+        .reconciled(vec![transaction_id])
+        .await?
+        .is_empty()
+    {
+        return Err(ApiError::Locked(json!(
+            {"transaction": "Reconciled transactions cannot be changed"}
+        )));
+    }
+
+    Ok(())
+}
+
+/// Transactions only become reconciled by finishing a reconciliation.
+fn settable_status(status: Option<TransactionStatus>) -> Result<(), ApiError> {
+    if matches!(status, Some(TransactionStatus::Reconciled)) {
+        return Err(ApiError::Validation(json!(
+            {"status": "Transactions are reconciled by finishing a reconciliation"}
+        )));
+    }
+
+    Ok(())
+}
+
+/// Checks the coordinates are on the map and tidies up the place name.
+fn valid_location(location: Option<Location>) -> Result<Option<Location>, ApiError> {
+    let Some(location) = location else {
+        return Ok(None);
+    };
+
+    if !(-90.0..=90.0).contains(&location.lat) || !(-180.0..=180.0).contains(&location.lon) {
+        return Err(ApiError::Validation(json!(
+            {"location": "Latitude must be within ±90 and longitude within ±180"}
+        )));
+    }
+
+    Ok(Some(Location {
+        place: location
+            .place
+            .map(|place| place.trim().to_string())
+            .filter(|place| !place.is_empty()),
+        ..location
+    }))
+}
+
+pub async fn owned_payee(
+    state: &ApiState,
+    user_id: RecordId,
+    payee_id: Option<String>,
+) -> Result<Option<RecordId>, ApiError> {
+    let Some(payee_id) = payee_id else {
+        return Ok(None);
+    };
+
+    let payee_id = RecordId::from_table_key("payee", payee_id);
+
+    if !(PayeeRepo::new(&state.db)
+        .user_owns(user_id, payee_id.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this payee".into(),
+        )));
+    }
+
+    Ok(Some(payee_id))
+}
+
+async fn owned_splits(
+    state: &ApiState,
+    user_id: RecordId,
+    amount: f64,
+    splits: Vec<SplitPayload>,
+) -> Result<Vec<Split>, ApiError> {
+    if splits.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let category_repo = CategoryRepo::new(&state.db);
+
+    let mut owned = Vec::<Split>::with_capacity(splits.len());
+
+    for split in splits {
+        let category = RecordId::from_table_key("category", split.category);
+
+        if split.amount <= 0.0 {
+            return Err(ApiError::Validation(json!(
+                {"splits": "Split amounts must be greater than zero"}
+            )));
+        }
+
+        if owned.iter().any(|s| s.category == category) {
+            return Err(ApiError::Validation(json!(
+                {"splits": "Each category can only appear once in the splits"}
+            )));
+        }
+
+        if !(category_repo
+            .user_owns(user_id.clone(), category.clone())
+            .await?)
+        {
+            return Err(ApiError::Db(DbError::NotFound(
+                "User does not own this category".into(),
+            )));
+        }
+
+        owned.push(Split {
+            category,
+            amount: split.amount,
+        });
+    }
+
+    let total = owned.iter().map(|s| s.amount).sum::<f64>();
+
+    if (total - amount).abs() > SPLIT_TOLERANCE {
+        return Err(ApiError::Validation(json!(
+            {"splits": "Split amounts must add up to the transaction amount"}
+        )));
+    }
+
+    Ok(owned)
+}
+
+pub async fn owned_tags(
+    state: &ApiState,
+    user_id: RecordId,
+    tags: Vec<String>,
+) -> Result<Vec<RecordId>, ApiError> {
+    let mut tag_ids = Vec::<RecordId>::with_capacity(tags.len());
+
+    for tag_id in tags
+        .into_iter()
+        .map(|tag| RecordId::from_table_key("tag", tag))
+    {
+        if !tag_ids.contains(&tag_id) {
+            tag_ids.push(tag_id);
+        }
+    }
+
+    if !tag_ids.is_empty()
+        && !(TagRepo::new(&state.db)
+            .user_owns_all(user_id, tag_ids.clone())
+            .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this tag".into(),
+        )));
+    }
+
+    Ok(tag_ids)
+}
+
+pub async fn owned_draft(
+    state: &ApiState,
+    user_id: RecordId,
+    payload: ItemPayload,
+) -> Result<TransactionDraft, ApiError> {
+    Ok(TransactionDraft {
+        account: owned_account(state, user_id.clone(), payload.account).await?,
+        splits: owned_splits(state, user_id.clone(), payload.amount, payload.splits).await?,
+        tags: owned_tags(state, user_id.clone(), payload.tags).await?,
+        payee: owned_payee(state, user_id, payload.payee).await?,
+        amount: payload.amount,
+        note: payload.note,
+        date: payload.date,
+    })
+}
+
+pub fn transaction(id: RecordId, draft: TransactionDraft) -> Transaction {
+    Transaction {
+        id,
+        amount: draft.amount,
+        note: draft.note,
+        date: draft.date,
+        account: draft.account,
+        splits: draft.splits,
+        tags: draft.tags,
+        payee: draft.payee,
+        category: None,
+        refund_of: None,
+        refund_kind: None,
+        installment: None,
+        installment_number: None,
+        status: None,
+        location: None,
+    }
+}
+
+/// Rejects `draft` with a conflict listing the user's transactions it looks
+/// like a second entry of.
+pub async fn check_duplicates(
+    state: &ApiState,
+    user_id: RecordId,
+    draft: &TransactionDraft,
+) -> Result<(), ApiError> {
+    let date = to_utc(&draft.date);
+
+    let matches = TransactionRepo::new(&state.db)
+        .summaries(
+            user_id,
+            (date - duplicates::window()).into(),
+            (date + duplicates::window()).into(),
+        )
+        .await?
+        .into_iter()
+        .filter(|other| {
+            duplicates::is_duplicate(draft.amount, &draft.date, draft.note.as_deref(), other)
+        })
+        .collect::<Vec<_>>();
+
+    if !matches.is_empty() {
+        return Err(ApiError::AlreadyExists(json!({ "duplicates": matches })));
+    }
+
+    Ok(())
 }
 
 pub async fn create(
     State(state): State<Arc<ApiState>>,
     Path(category_id): Path<String>,
+    Query(check): Query<DuplicateCheck>,
     auth: AuthUser,
     Json(payload): Json<ItemPayload>,
 ) -> Result<impl IntoResponse, ApiError> {
@@ -37,7 +335,7 @@ pub async fn create(
     let category_id = RecordId::from_table_key("category", category_id);
 
     if !(category_repo
-        .user_owns(auth.user_id, category_id.clone())
+        .user_owns(auth.user_id.clone(), category_id.clone())
         .await?)
     {
         return Err(ApiError::Db(DbError::NotFound(
@@ -45,22 +343,68 @@ pub async fn create(
         )));
     }
 
+    let status = payload.status;
+    settable_status(status)?;
+
+    let location = valid_location(payload.location.clone())?;
+
+    let mut draft = owned_draft(&state, auth.user_id.clone(), payload).await?;
+
+    // Split transactions were categorized by hand, so rules only add tags.
+    let outcome = RuleSet::new(
+        RuleRepo::new(&state.db)
+            .list_active(auth.user_id.clone())
+            .await?,
+    )
+    .evaluate(&Subject {
+        amount: draft.amount,
+        note: draft.note.as_deref(),
+        payee: draft.payee.as_ref(),
+    });
+
+    for tag in outcome.tags {
+        if !draft.tags.contains(&tag) {
+            draft.tags.push(tag);
+        }
+    }
+
+    let filed_under = outcome
+        .category
+        .filter(|category| draft.splits.is_empty() && *category != category_id);
+
+    if !check.allow_duplicate {
+        check_duplicates(&state, auth.user_id, &draft).await?;
+    }
+
     let transaction_id = transaction_repo
-        .create(
-            category_id,
-            payload.amount,
-            payload.note.clone(),
-            payload.date.clone(),
-        )
+        .create(filed_under.clone().unwrap_or(category_id), draft.clone())
         .await?;
 
+    let status = match status {
+        Some(status) => {
+            transaction_repo
+                .set_status(transaction_id.clone(), status)
+                .await?;
+
+            status
+        }
+        None if to_utc(&draft.date) > Utc::now() => TransactionStatus::Pending,
+        None => TransactionStatus::Cleared,
+    };
+
+    if location.is_some() {
+        transaction_repo
+            .set_location(transaction_id.clone(), location.clone())
+            .await?;
+    }
+
     Ok((
         StatusCode::CREATED,
         Json(Transaction {
-            id: transaction_id,
-            amount: payload.amount,
-            note: payload.note,
-            date: payload.date,
+            category: filed_under,
+            status: Some(status),
+            location,
+            ..transaction(transaction_id, draft)
         }),
     ))
 }
@@ -75,27 +419,41 @@ pub async fn edit(
 
     let transaction_id = RecordId::from_table_key("transaction", transaction_id);
 
-    if !(repo.user_owns(auth.user_id, transaction_id.clone()).await?) {
+    if !(repo
+        .user_owns(auth.user_id.clone(), transaction_id.clone())
+        .await?)
+    {
         return Err(ApiError::Db(DbError::NotFound(
             "User does not own this transaction".into(),
         )));
     }
 
+    ensure_unlocked(&state, transaction_id.clone()).await?;
+
+    let status = payload.status;
+    settable_status(status)?;
+
+    let location = valid_location(payload.location.clone())?;
+
+    let draft = owned_draft(&state, auth.user_id.clone(), payload).await?;
+
+    check_refund_total(&state, transaction_id.clone(), draft.amount).await?;
+
     repo.edit(
         transaction_id.clone(),
-        payload.amount,
-        payload.note.clone(),
-        payload.date.clone(),
+        auth.user_id,
+        draft.clone(),
+        status,
+        Some(location.clone()),
     )
     .await?;
 
     Ok((
         StatusCode::OK,
         Json(Transaction {
-            id: transaction_id,
-            amount: payload.amount,
-            note: payload.note,
-            date: payload.date,
+            status,
+            location,
+            ..transaction(transaction_id, draft)
         }),
     ))
 }
@@ -115,17 +473,47 @@ pub async fn delete(
         )));
     }
 
+    ensure_unlocked(&state, transaction_id.clone()).await?;
+
     repo.delete(transaction_id).await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+pub async fn restore(
+    State(state): State<Arc<ApiState>>,
+    Path((_, transaction_id)): Path<(String, String)>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo = TransactionRepo::new(&state.db);
+
+    let transaction_id = RecordId::from_table_key("transaction", transaction_id);
+
+    if !(repo
+        .user_owns_trashed(auth.user_id, transaction_id.clone())
+        .await?)
+    {
+        return Err(ApiError::Db(DbError::NotFound(
+            "User does not own this transaction".into(),
+        )));
+    }
+
+    repo.restore(transaction_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 pub async fn list(
     State(state): State<Arc<ApiState>>,
     Path(category_id): Path<String>,
     Query(range): Query<DateRange>,
+    Query(filter): Query<TagFilter>,
+    Query(tz): Query<TimezoneQuery>,
     auth: AuthUser,
 ) -> Result<impl IntoResponse, ApiError> {
+    let tz = tz.resolve(&state, auth.user_id.clone()).await?;
+    let (start, end) = range.resolve(tz)?;
+
     let category_repo = CategoryRepo::new(&state.db);
     let transaction_repo = TransactionRepo::new(&state.db);
 
@@ -141,7 +529,7 @@ pub async fn list(
     }
 
     let transactions = transaction_repo
-        .list(category_id, range.start, range.end)
+        .list(category_id, start, end, filter.tag_id())
         .await?;
 
     Ok(Json(transactions))
@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use axum::{Json, extract::State, response::IntoResponse};
+
+use crate::{
+    api::{ApiError, ApiState, auth::extractor::AuthUser},
+    db::repo::{CategoryRepo, transaction_repo::TransactionRepo},
+    models::Trash,
+};
+
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let categories = CategoryRepo::new(&state.db)
+        .list_trashed(auth.user_id.clone())
+        .await?;
+    let transactions = TransactionRepo::new(&state.db)
+        .list_trashed(auth.user_id)
+        .await?;
+
+    Ok(Json(Trash {
+        categories,
+        transactions,
+    }))
+}
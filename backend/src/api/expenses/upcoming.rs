@@ -0,0 +1,78 @@
+use std::{collections::HashSet, sync::Arc};
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    response::IntoResponse,
+};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+
+use crate::{
+    api::{ApiError, ApiState, auth::extractor::AuthUser},
+    db::repo::{RecurringRepo, transaction_repo::TransactionRepo},
+    models::UpcomingBill,
+    recurrence::{self, to_utc},
+};
+
+const MAX_DAYS: i64 = 366;
+
+#[derive(Deserialize)]
+pub struct UpcomingQuery {
+    #[serde(default = "default_days")]
+    days: i64,
+}
+
+fn default_days() -> i64 {
+    30
+}
+
+/// Lists what falls due within the next `days` days: pending transactions,
+/// overdue ones included, and the occurrences recurring transactions have yet
+/// to create.
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    Query(query): Query<UpcomingQuery>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, ApiError> {
+    let recurring_repo = RecurringRepo::new(&state.db);
+
+    let now = Utc::now();
+    let end = now + Duration::days(query.days.clamp(1, MAX_DAYS));
+
+    let mut bills = TransactionRepo::new(&state.db)
+        .upcoming(auth.user_id.clone(), end.into())
+        .await?;
+
+    for recurring in recurring_repo.list(auth.user_id).await? {
+        if recurring.stopped {
+            continue;
+        }
+
+        let skipped = recurring_repo
+            .skipped_dates(recurring.id.clone())
+            .await?
+            .iter()
+            .map(to_utc)
+            .collect::<HashSet<_>>();
+
+        bills.extend(
+            (recurring.occurrences..)
+                .map_while(|n| recurrence::occurrence(&recurring.schedule, n))
+                .take_while(|date| *date <= end)
+                .filter(|date| !skipped.contains(date))
+                .map(|date| UpcomingBill {
+                    category: recurring.category.clone(),
+                    amount: recurring.amount,
+                    note: recurring.note.clone(),
+                    date: date.into(),
+                    transaction: None,
+                    recurring: Some(recurring.id.clone()),
+                }),
+        );
+    }
+
+    bills.sort_by_key(|bill| to_utc(&bill.date));
+
+    Ok(Json(bills))
+}
@@ -1,3 +1,4 @@
+mod accounts;
 mod auth;
 mod defs;
 mod error;
@@ -27,7 +28,10 @@ pub async fn router() -> Result<Router, ApiError> {
         jwt_verifier: JwtVerifier::hs256_from_env(),
     });
 
+    crate::scheduler::spawn(api_state.db.clone());
+
     Ok(Router::new()
+        .nest("/accounts", accounts::router(api_state.clone()))
         .nest("/auth", auth::router(api_state.clone()))
         .nest("/expenses", expenses::router(api_state.clone()))
         .with_state(api_state))
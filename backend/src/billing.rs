@@ -0,0 +1,33 @@
+use chrono::{Datelike, Months, NaiveDate};
+
+/// Returns the statement date for `statement_day` in the month of `date`,
+/// falling back to the last day of months too short to have it.
+fn statement_date(date: NaiveDate, statement_day: u32) -> Option<NaiveDate> {
+    (1..=statement_day).rev().find_map(|day| date.with_day(day))
+}
+
+/// Returns the `count` most recent billing cycles of a card with the given
+/// `statement_day`, newest first and starting with the one `today` falls in,
+/// as inclusive `(opens, closes)` date pairs. A cycle closes on its statement
+/// date and the next one opens the day after.
+pub fn cycles(statement_day: u32, today: NaiveDate, count: u32) -> Vec<(NaiveDate, NaiveDate)> {
+    let latest = match statement_date(today, statement_day) {
+        Some(closes) if today <= closes => Some(today),
+        _ => today.checked_add_months(Months::new(1)),
+    };
+
+    let Some(latest) = latest else {
+        return Vec::new();
+    };
+
+    (0..count)
+        .map_while(|n| {
+            let month = latest.checked_sub_months(Months::new(n))?;
+            let closes = statement_date(month, statement_day)?;
+            let previous =
+                statement_date(month.checked_sub_months(Months::new(1))?, statement_day)?;
+
+            Some((previous.succ_opt()?, closes))
+        })
+        .collect()
+}
@@ -0,0 +1,214 @@
+use surrealdb::RecordId;
+
+use crate::models::{Category, CategoryName};
+
+/// How many levels deep categories can be nested, counting the top level.
+pub const MAX_DEPTH: usize = 3;
+
+/// Separates the levels of a category path like `Food > Groceries`.
+pub const PATH_SEPARATOR: char = '>';
+
+fn parent_of<'a>(
+    parents: &'a [(RecordId, Option<RecordId>)],
+    id: &RecordId,
+) -> Option<&'a RecordId> {
+    parents
+        .iter()
+        .find(|(category, _)| category == id)
+        .and_then(|(_, parent)| parent.as_ref())
+}
+
+/// Levels in the subtree rooted at `category`, counting itself.
+fn height(parents: &[(RecordId, Option<RecordId>)], category: &RecordId, depth: usize) -> usize {
+    // Stops on data that already nests too deep or has a cycle.
+    if depth > MAX_DEPTH {
+        return depth;
+    }
+
+    parents
+        .iter()
+        .filter(|(_, parent)| parent.as_ref() == Some(category))
+        .map(|(child, _)| height(parents, child, depth + 1))
+        .max()
+        .unwrap_or(depth)
+}
+
+/// `category` followed by its parent, grandparent and so on.
+fn lineage<'a>(
+    parents: &'a [(RecordId, Option<RecordId>)],
+    category: &'a RecordId,
+) -> Vec<&'a RecordId> {
+    let mut lineage = vec![category];
+
+    while let Some(ancestor) = parent_of(parents, lineage[lineage.len() - 1]) {
+        if lineage.contains(&ancestor) {
+            break;
+        }
+
+        lineage.push(ancestor);
+    }
+
+    lineage
+}
+
+/// Whether `category` is nested, at any depth, under `ancestor`.
+pub fn is_descendant(
+    parents: &[(RecordId, Option<RecordId>)],
+    category: &RecordId,
+    ancestor: &RecordId,
+) -> bool {
+    lineage(parents, category)[1..].contains(&ancestor)
+}
+
+/// Checks that `category` (`None` when it is being created) can be moved
+/// under `parent`, given every category's current parent in `parents`.
+pub fn check_parent(
+    parents: &[(RecordId, Option<RecordId>)],
+    category: Option<&RecordId>,
+    parent: &RecordId,
+) -> Result<(), &'static str> {
+    let ancestors = lineage(parents, parent);
+
+    if let Some(category) = category
+        && ancestors.contains(&category)
+    {
+        return Err("A category cannot be nested under itself or its subcategories");
+    }
+
+    let height = category.map_or(1, |category| height(parents, category, 1));
+
+    if ancestors.len() + height > MAX_DEPTH {
+        return Err("Categories can only be nested three levels deep");
+    }
+
+    Ok(())
+}
+
+/// Moves the categories in `pending` whose parent is `category` under it,
+/// recursively, and rolls their totals up into it.
+fn attach(mut category: Category, pending: &mut Vec<Category>) -> Category {
+    let (children, rest) = pending
+        .drain(..)
+        .partition::<Vec<_>, _>(|child| child.parent.as_ref() == Some(&category.id));
+    *pending = rest;
+
+    category.children = children
+        .into_iter()
+        .map(|child| attach(child, pending))
+        .collect();
+
+    category.total_amount = category.amount
+        + category
+            .children
+            .iter()
+            .map(|child| child.total_amount)
+            .sum::<f64>();
+    category.total_transactions = category.transactions
+        + category
+            .children
+            .iter()
+            .map(|child| child.total_transactions)
+            .sum::<usize>();
+
+    category
+}
+
+/// Nests `categories` under their parents and fills in the rolled-up totals.
+/// Categories whose parent isn't in the list are returned at the top level.
+pub fn build(categories: Vec<Category>) -> Vec<Category> {
+    let (roots, mut pending) = categories
+        .into_iter()
+        .partition::<Vec<_>, _>(|category| category.parent.is_none());
+
+    let mut tree = roots
+        .into_iter()
+        .map(|root| attach(root, &mut pending))
+        .collect::<Vec<_>>();
+
+    // Whatever is left has a parent outside the list. Starting from those
+    // whose parent isn't left either keeps their own subtrees together; the
+    // fallback only matters for cycles.
+    while !pending.is_empty() {
+        let orphan = pending
+            .iter()
+            .position(|category| {
+                !pending
+                    .iter()
+                    .any(|other| category.parent.as_ref() == Some(&other.id))
+            })
+            .unwrap_or(0);
+
+        let orphan = pending.remove(orphan);
+        tree.push(attach(orphan, &mut pending));
+    }
+
+    tree
+}
+
+/// Finds the category named by `path`, compared case-insensitively. A path like
+/// `Food > Groceries` is followed from the top level down; a bare name prefers
+/// the top-level category and is ambiguous when only several subcategories
+/// carry it.
+pub fn find<'a>(
+    categories: &'a [CategoryName],
+    path: &str,
+) -> Result<Option<&'a CategoryName>, String> {
+    let levels = path
+        .split(PATH_SEPARATOR)
+        .map(|level| level.trim().to_lowercase())
+        .collect::<Vec<_>>();
+
+    if let [name] = levels.as_slice() {
+        let matches = categories
+            .iter()
+            .filter(|category| category.name.to_lowercase() == *name)
+            .collect::<Vec<_>>();
+
+        return match matches.iter().find(|category| category.parent.is_none()) {
+            Some(category) => Ok(Some(category)),
+            None if matches.len() > 1 => {
+                let name = path.trim();
+
+                Err(format!(
+                    "Category \"{name}\" is ambiguous, use a path like \"Parent {PATH_SEPARATOR} {name}\""
+                ))
+            }
+            None => Ok(matches.first().copied()),
+        };
+    }
+
+    let mut found = None::<&CategoryName>;
+
+    for name in &levels {
+        let parent = found.map(|category| &category.id);
+
+        found = categories.iter().find(|category| {
+            category.parent.as_ref() == parent && category.name.to_lowercase() == *name
+        });
+
+        if found.is_none() {
+            return Ok(None);
+        }
+    }
+
+    Ok(found)
+}
+
+/// Splits a path of categories to be created into its levels, checking that
+/// none is empty and that it doesn't nest too deep.
+pub fn new_path(path: &str) -> Result<Vec<String>, &'static str> {
+    let levels = path
+        .split(PATH_SEPARATOR)
+        .map(|level| level.trim().to_string())
+        .collect::<Vec<_>>();
+
+    if levels.iter().any(String::is_empty) {
+        return Err("Category names cannot be empty");
+    }
+
+    if levels.len() > MAX_DEPTH {
+        return Err("Categories can only be nested three levels deep");
+    }
+
+    Ok(levels)
+}
@@ -0,0 +1,85 @@
+use std::{collections::BTreeMap, f64::consts::PI};
+
+use crate::models::{
+    GeoCluster, GeoFeature, GeoFeatureCollection, GeoPoint, GeoProperties, GeoTransaction,
+};
+
+/// From this zoom level on every transaction gets its own marker.
+pub const MAX_CLUSTER_ZOOM: u8 = 15;
+
+/// Grid cells per side of a 256px map tile, so a cluster covers about 64px.
+const CELLS_PER_TILE: f64 = 4.0;
+
+/// The furthest latitude Web Mercator maps draw.
+const MAX_LATITUDE: f64 = 85.051_128_78;
+
+/// Returns the grid cell `(lat, lon)` falls in at `zoom`, in Web Mercator so
+/// that cells are roughly square on screen.
+fn cell(lat: f64, lon: f64, zoom: u8) -> (i64, i64) {
+    let cells = 2f64.powi(zoom.into()) * CELLS_PER_TILE;
+    let lat = lat.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+
+    let x = (lon + 180.0) / 360.0;
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0;
+
+    ((x * cells).floor() as i64, (y * cells).floor() as i64)
+}
+
+fn feature(lat: f64, lon: f64, properties: GeoProperties) -> GeoFeature {
+    GeoFeature {
+        kind: "Feature",
+        geometry: GeoPoint {
+            kind: "Point",
+            coordinates: [lon, lat],
+        },
+        properties,
+    }
+}
+
+/// Turns `transactions` into GeoJSON features. Below `MAX_CLUSTER_ZOOM`
+/// transactions sharing a grid cell are merged into one cluster at their
+/// average position, carrying their count and total amount.
+pub fn features(transactions: Vec<GeoTransaction>, zoom: u8) -> GeoFeatureCollection {
+    let features = if zoom >= MAX_CLUSTER_ZOOM {
+        transactions
+            .into_iter()
+            .map(|t| feature(t.lat, t.lon, GeoProperties::Transaction(t)))
+            .collect()
+    } else {
+        let mut cells = BTreeMap::<(i64, i64), Vec<GeoTransaction>>::new();
+
+        for transaction in transactions {
+            cells
+                .entry(cell(transaction.lat, transaction.lon, zoom))
+                .or_default()
+                .push(transaction);
+        }
+
+        cells
+            .into_values()
+            .map(|mut members| {
+                if members.len() == 1 {
+                    let t = members.remove(0);
+                    return feature(t.lat, t.lon, GeoProperties::Transaction(t));
+                }
+
+                let count = members.len();
+
+                feature(
+                    members.iter().map(|t| t.lat).sum::<f64>() / count as f64,
+                    members.iter().map(|t| t.lon).sum::<f64>() / count as f64,
+                    GeoProperties::Cluster(GeoCluster {
+                        cluster: true,
+                        count,
+                        amount: members.iter().map(|t| t.amount).sum(),
+                    }),
+                )
+            })
+            .collect()
+    };
+
+    GeoFeatureCollection {
+        kind: "FeatureCollection",
+        features,
+    }
+}
@@ -11,6 +11,10 @@ pub struct Config {
 
     pub surreal: SurrealConfig,
     pub jwt: JwtConfig,
+    pub scheduler: SchedulerConfig,
+    pub attachments: AttachmentsConfig,
+    pub duplicates: DuplicatesConfig,
+    pub trash: TrashConfig,
 }
 
 #[derive(Debug)]
@@ -30,6 +34,29 @@ pub struct JwtConfig {
     pub refresh_ttl: u64,
 }
 
+#[derive(Debug)]
+pub struct SchedulerConfig {
+    pub interval: u64,
+}
+
+#[derive(Debug)]
+pub struct AttachmentsConfig {
+    pub dir: String,
+    pub max_size: usize,
+}
+
+#[derive(Debug)]
+pub struct DuplicatesConfig {
+    pub window_days: i64,
+    pub amount_tolerance: f64,
+    pub note_similarity: f64,
+}
+
+#[derive(Debug)]
+pub struct TrashConfig {
+    pub retention_days: i64,
+}
+
 #[inline]
 pub fn config() -> &'static Config {
     CONFIG.get_or_init(|| {
@@ -49,6 +76,18 @@ pub fn config() -> &'static Config {
         let jwt_access_ttl = env_default!("JWT_ACCESS_TTL" as u64 = 900);
         let jwt_refresh_ttl = env_default!("JWT_REFRESH_TTL" as u64 = 604800);
 
+        let scheduler_interval = env_default!("SCHEDULER_INTERVAL" as u64 = 60);
+
+        let attachments_dir = env_default!("ATTACHMENTS_DIR" = "attachments");
+        let attachments_max_size = env_default!("ATTACHMENTS_MAX_SIZE" as usize = 10485760);
+
+        let duplicates_window_days = env_default!("DUPLICATES_WINDOW_DAYS" as i64 = 2);
+        let duplicates_amount_tolerance =
+            env_default!("DUPLICATES_AMOUNT_TOLERANCE" as f64 = 0.02);
+        let duplicates_note_similarity = env_default!("DUPLICATES_NOTE_SIMILARITY" as f64 = 0.5);
+
+        let trash_retention_days = env_default!("TRASH_RETENTION_DAYS" as i64 = 30);
+
         if !(surreal_url.starts_with("ws://")
             || surreal_url.starts_with("wss://")
             || surreal_url.starts_with("http://")
@@ -80,6 +119,25 @@ pub fn config() -> &'static Config {
                 access_ttl: jwt_access_ttl,
                 refresh_ttl: jwt_refresh_ttl,
             },
+
+            scheduler: SchedulerConfig {
+                interval: scheduler_interval,
+            },
+
+            attachments: AttachmentsConfig {
+                dir: attachments_dir,
+                max_size: attachments_max_size,
+            },
+
+            duplicates: DuplicatesConfig {
+                window_days: duplicates_window_days,
+                amount_tolerance: duplicates_amount_tolerance,
+                note_similarity: duplicates_note_similarity,
+            },
+
+            trash: TrashConfig {
+                retention_days: trash_retention_days,
+            },
         }
     })
 }
@@ -0,0 +1,311 @@
+use serde_json::json;
+use surrealdb::{Datetime, RecordId};
+
+use crate::{
+    db::{ApiDb, DbError},
+    models::{Account, AccountEntry, AccountKind, Transfer},
+};
+
+pub struct AccountRepo<'a> {
+    db: &'a ApiDb,
+}
+
+impl<'a> AccountRepo<'a> {
+    pub fn new(db: &'a ApiDb) -> Self {
+        Self { db }
+    }
+
+    pub async fn exists(&self, user_id: RecordId, name: String) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $user->user_account.out
+            WHERE string::lowercase(name) = string::lowercase($name)
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("name", name))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn exists_excluding(
+        &self,
+        user_id: RecordId,
+        name: String,
+        exclude_id: RecordId,
+    ) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $user->user_account.out
+            WHERE
+                string::lowercase(name) = string::lowercase($name)
+                AND id != $exclude
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("name", name))
+            .bind(("exclude", exclude_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn user_owns(
+        &self,
+        user_id: RecordId,
+        account_id: RecordId,
+    ) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $user->user_account
+            WHERE out = $account
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("account", account_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn create(
+        &self,
+        user_id: RecordId,
+        name: String,
+        kind: AccountKind,
+        opening_balance: f64,
+        statement_day: Option<u32>,
+    ) -> Result<RecordId, DbError> {
+        let sql = "fn::add_account($user, $name, $kind, $opening_balance, $statement_day);";
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("name", name))
+            .bind(("kind", kind))
+            .bind(("opening_balance", opening_balance))
+            .bind(("statement_day", statement_day))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotCreated("account".into()))
+    }
+
+    pub async fn edit(
+        &self,
+        id: RecordId,
+        name: String,
+        kind: AccountKind,
+        opening_balance: f64,
+        statement_day: Option<u32>,
+    ) -> Result<(), DbError> {
+        let sql = r#"
+        UPDATE ONLY $account SET
+            name = $name,
+            kind = $kind,
+            opening_balance = $opening_balance,
+            statement_day = $statement_day;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("account", id))
+            .bind(("name", name))
+            .bind(("kind", kind))
+            .bind(("opening_balance", opening_balance))
+            .bind(("statement_day", statement_day))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, account_id: RecordId) -> Result<(), DbError> {
+        let sql = r#"
+        DELETE (SELECT VALUE id FROM $account<-user_account);
+        DELETE ONLY $account RETURN BEFORE;
+        "#;
+
+        self.db.query(sql).bind(("account", account_id)).await?;
+
+        Ok(())
+    }
+
+    pub async fn list(&self, user_id: RecordId) -> Result<Vec<Account>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            name,
+            kind,
+            opening_balance,
+            statement_day,
+            opening_balance
+                - math::sum((SELECT VALUE IF refund_of THEN -amount ELSE amount END FROM transaction WHERE account = $parent.id AND deleted_at = NONE))
+                - math::sum((SELECT VALUE amount FROM transfer WHERE from_account = $parent.id))
+                + math::sum((SELECT VALUE amount FROM transfer WHERE to_account = $parent.id))
+                AS balance
+        FROM $user->user_account.out
+        ORDER BY name;
+        "#;
+
+        Ok(self.db.query(sql).bind(("user", user_id)).await?.take(0)?)
+    }
+
+    pub async fn statement_day(&self, id: RecordId) -> Result<Option<u32>, DbError> {
+        let sql = "SELECT VALUE statement_day FROM ONLY $account;";
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("account", id))
+            .await?
+            .take::<Option<_>>(0)?)
+    }
+
+    /// Lists the live transactions charged to the account within
+    /// `start..end`, with refunds as negative amounts.
+    pub async fn entries(
+        &self,
+        id: RecordId,
+        start: Datetime,
+        end: Datetime,
+    ) -> Result<Vec<AccountEntry>, DbError> {
+        let sql = r#"
+        SELECT
+            date,
+            IF refund_of THEN -amount ELSE amount END AS amount
+        FROM transaction
+        WHERE
+            account = $account
+            AND date >= $start
+            AND date < $end
+            AND deleted_at = NONE
+        ORDER BY date;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("account", id))
+            .bind(("start", start))
+            .bind(("end", end))
+            .await?
+            .take(0)?)
+    }
+
+    pub async fn user_owns_transfer(
+        &self,
+        user_id: RecordId,
+        transfer_id: RecordId,
+    ) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY transfer
+            WHERE
+                id = $transfer
+                AND from_account IN $user->user_account.out
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("transfer", transfer_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn create_transfer(
+        &self,
+        from_account: RecordId,
+        to_account: RecordId,
+        amount: f64,
+        note: Option<String>,
+        date: Datetime,
+    ) -> Result<RecordId, DbError> {
+        let sql = r#"
+        CREATE transfer SET
+            from_account = $from_account,
+            to_account = $to_account,
+            amount = $amount,
+            note = $note,
+            date = $date
+        RETURN VALUE id;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("from_account", from_account))
+            .bind(("to_account", to_account))
+            .bind(("amount", amount))
+            .bind(("note", note))
+            .bind(("date", date))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotCreated("transfer".into()))
+    }
+
+    pub async fn delete_transfer(&self, id: RecordId) -> Result<(), DbError> {
+        let sql = "DELETE ONLY $transfer RETURN BEFORE;";
+
+        self.db.query(sql).bind(("transfer", id)).await?;
+
+        Ok(())
+    }
+
+    pub async fn list_transfers(
+        &self,
+        account_id: RecordId,
+        start: Datetime,
+        end: Datetime,
+    ) -> Result<Vec<Transfer>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            from_account,
+            to_account,
+            amount,
+            note,
+            date
+        FROM transfer
+        WHERE
+            (from_account = $account OR to_account = $account)
+            AND date IN $start..=$end
+        ORDER BY date DESC;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("account", account_id))
+            .bind(("start", start))
+            .bind(("end", end))
+            .await?
+            .take(0)?)
+    }
+}
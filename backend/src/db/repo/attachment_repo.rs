@@ -0,0 +1,128 @@
+use serde_json::json;
+use surrealdb::RecordId;
+
+use crate::{
+    db::{ApiDb, DbError},
+    models::Attachment,
+};
+
+pub struct AttachmentRepo<'a> {
+    db: &'a ApiDb,
+}
+
+impl<'a> AttachmentRepo<'a> {
+    pub fn new(db: &'a ApiDb) -> Self {
+        Self { db }
+    }
+
+    pub async fn create(
+        &self,
+        transaction_id: RecordId,
+        name: String,
+        mime: String,
+        size: u64,
+        hash: String,
+    ) -> Result<Attachment, DbError> {
+        let sql = r#"
+        CREATE ONLY attachment SET
+            transaction = $transaction,
+            name = $name,
+            mime = $mime,
+            size = $size,
+            hash = $hash
+        RETURN
+            id,
+            name,
+            mime,
+            size,
+            hash,
+            created_at;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("transaction", transaction_id))
+            .bind(("name", name))
+            .bind(("mime", mime))
+            .bind(("size", size))
+            .bind(("hash", hash))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotCreated("attachment".into()))
+    }
+
+    pub async fn get(&self, transaction_id: RecordId, id: RecordId) -> Result<Attachment, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            name,
+            mime,
+            size,
+            hash,
+            created_at
+        FROM ONLY attachment
+        WHERE id = $attachment AND transaction = $transaction
+        LIMIT 1;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("transaction", transaction_id))
+            .bind(("attachment", id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotFound(
+                json!({"attachment": "No attachment found with that id"}),
+            ))
+    }
+
+    pub async fn list(&self, transaction_id: RecordId) -> Result<Vec<Attachment>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            name,
+            mime,
+            size,
+            hash,
+            created_at
+        FROM attachment
+        WHERE transaction = $transaction
+        ORDER BY created_at;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("transaction", transaction_id))
+            .await?
+            .take(0)?)
+    }
+
+    pub async fn delete(&self, id: RecordId) -> Result<(), DbError> {
+        let sql = "DELETE ONLY $attachment RETURN BEFORE;";
+
+        self.db.query(sql).bind(("attachment", id)).await?;
+
+        Ok(())
+    }
+
+    /// Hashes queued by the `attachment_delete` event whose files are no
+    /// longer referenced by any attachment.
+    pub async fn list_purgeable(&self) -> Result<Vec<String>, DbError> {
+        let sql = r#"
+        SELECT VALUE hash
+        FROM attachment_purge
+        WHERE hash NOT IN (SELECT VALUE hash FROM attachment);
+        "#;
+
+        Ok(self.db.query(sql).await?.take(0)?)
+    }
+
+    pub async fn clear_purge(&self, hash: String) -> Result<(), DbError> {
+        let sql = "DELETE attachment_purge WHERE hash = $hash;";
+
+        self.db.query(sql).bind(("hash", hash)).await?;
+
+        Ok(())
+    }
+}
@@ -1,9 +1,16 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use serde::Deserialize;
 use serde_json::json;
 use surrealdb::{Datetime, RecordId};
 
 use crate::{
+    category_tree,
     db::{ApiDb, DbError},
-    models::{Category, Expense, ExpensesOverview},
+    models::{Category, CategoryName, Expense, ExpensesOverview, TagTotal, TrashedCategory},
+    recurrence::to_utc,
 };
 
 pub struct CategoryRepo<'a> {
@@ -15,12 +22,22 @@ impl<'a> CategoryRepo<'a> {
         Self { db }
     }
 
-    pub async fn exists(&self, user_id: RecordId, name: String) -> Result<bool, DbError> {
+    /// Names only have to be unique among categories with the same parent, so
+    /// `Food > Other` and `Travel > Other` can coexist.
+    pub async fn exists(
+        &self,
+        user_id: RecordId,
+        name: String,
+        parent_id: Option<RecordId>,
+    ) -> Result<bool, DbError> {
         let sql = r#"
         (
             SELECT VALUE id
             FROM ONLY $user->user_category.out
-            WHERE string::lowercase(name) = string::lowercase($name)
+            WHERE
+                string::lowercase(name) = string::lowercase($name)
+                AND parent = $parent
+                AND deleted_at = NONE
             LIMIT 1
         ) != NONE;
         "#;
@@ -29,6 +46,7 @@ impl<'a> CategoryRepo<'a> {
             .query(sql)
             .bind(("user", user_id))
             .bind(("name", name))
+            .bind(("parent", parent_id))
             .await?
             .take::<Option<_>>(0)?
             .ok_or(DbError::Unknown(json!({
@@ -40,6 +58,7 @@ impl<'a> CategoryRepo<'a> {
         &self,
         user_id: RecordId,
         name: String,
+        parent_id: Option<RecordId>,
         exclude_id: RecordId,
     ) -> Result<bool, DbError> {
         let sql = r#"
@@ -48,7 +67,9 @@ impl<'a> CategoryRepo<'a> {
             FROM ONLY $user->user_category.out
             WHERE
                 string::lowercase(name) = string::lowercase($name)
+                AND parent = $parent
                 AND id != $exclude
+                AND deleted_at = NONE
             LIMIT 1
         ) != NONE;
         "#;
@@ -57,6 +78,7 @@ impl<'a> CategoryRepo<'a> {
             .query(sql)
             .bind(("user", user_id))
             .bind(("name", name))
+            .bind(("parent", parent_id))
             .bind(("exclude", exclude_id))
             .await?
             .take::<Option<_>>(0)?
@@ -74,7 +96,7 @@ impl<'a> CategoryRepo<'a> {
         (
             SELECT VALUE id
             FROM ONLY $user->user_category
-            WHERE out = $category
+            WHERE out = $category AND out.deleted_at = NONE
             LIMIT 1
         ) != NONE;
         "#;
@@ -90,62 +112,337 @@ impl<'a> CategoryRepo<'a> {
             })))
     }
 
+    pub async fn user_owns_trashed(
+        &self,
+        user_id: RecordId,
+        category_id: RecordId,
+    ) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $user->user_category
+            WHERE out = $category AND out.deleted_at != NONE
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("category", category_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn list_names(&self, user_id: RecordId) -> Result<Vec<CategoryName>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            name,
+            parent
+        FROM $user->user_category.out
+        WHERE deleted_at = NONE;
+        "#;
+
+        Ok(self.db.query(sql).bind(("user", user_id)).await?.take(0)?)
+    }
+
+    pub async fn name(&self, category_id: RecordId) -> Result<String, DbError> {
+        let sql = "SELECT VALUE name FROM ONLY $category;";
+
+        self.db
+            .query(sql)
+            .bind(("category", category_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotFound(
+                json!({"category": "No category found with that id"}),
+            ))
+    }
+
+    /// Pairs each of the user's live categories with its parent.
+    pub async fn parents(
+        &self,
+        user_id: RecordId,
+    ) -> Result<Vec<(RecordId, Option<RecordId>)>, DbError> {
+        #[derive(Deserialize)]
+        struct Node {
+            id: RecordId,
+            parent: Option<RecordId>,
+        }
+
+        let sql = r#"
+        SELECT
+            id,
+            parent
+        FROM $user->user_category.out
+        WHERE deleted_at = NONE;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("user", user_id))
+            .await?
+            .take::<Vec<Node>>(0)?
+            .into_iter()
+            .map(|node| (node.id, node.parent))
+            .collect())
+    }
+
+    /// The category's parent, unless that is in the trash.
+    pub async fn live_parent(&self, category_id: RecordId) -> Result<Option<RecordId>, DbError> {
+        let sql = r#"
+        SELECT VALUE IF parent.deleted_at = NONE THEN parent END
+        FROM ONLY $category;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("category", category_id))
+            .await?
+            .take::<Option<_>>(0)?)
+    }
+
+    pub async fn has_children(&self, category_id: RecordId) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY category
+            WHERE parent = $category AND deleted_at = NONE
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("category", category_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
     pub async fn create(
         &self,
         user_id: RecordId,
         name: String,
         icon: String,
+        parent_id: Option<RecordId>,
     ) -> Result<RecordId, DbError> {
-        let sql = "fn::add_category($user, $name, $icon);";
+        let sql = "fn::add_category($user, $name, $icon, $parent);";
 
         self.db
             .query(sql)
             .bind(("user", user_id))
             .bind(("name", name))
             .bind(("icon", icon))
+            .bind(("parent", parent_id))
             .await?
             .take::<Option<_>>(0)?
             .ok_or(DbError::NotCreated("category".into()))
     }
 
-    pub async fn edit(&self, id: RecordId, name: String, icon: String) -> Result<(), DbError> {
-        let sql = "UPDATE ONLY $category SET name = $name, icon = $icon;";
+    pub async fn edit(
+        &self,
+        id: RecordId,
+        name: String,
+        icon: String,
+        parent_id: Option<RecordId>,
+    ) -> Result<(), DbError> {
+        let sql = "UPDATE ONLY $category SET name = $name, icon = $icon, parent = $parent;";
 
         self.db
             .query(sql)
             .bind(("category", id))
             .bind(("name", name))
             .bind(("icon", icon))
+            .bind(("parent", parent_id))
             .await?;
 
         Ok(())
     }
 
+    /// Moves the category to the trash along with its transactions that aren't
+    /// already there, so that restoring it brings back exactly those.
     pub async fn delete(&self, category_id: RecordId) -> Result<(), DbError> {
         let sql = r#"
-        DELETE (SELECT VALUE id FROM $category<-user_category->category_transaction);
-        DELETE ONLY $category RETURN BEFORE;
+        BEGIN TRANSACTION;
+        UPDATE $category<-user_category->category_transaction.out SET
+            deleted_at = time::now(),
+            trashed_with = $category
+        WHERE deleted_at = NONE;
+        UPDATE ONLY $category SET deleted_at = time::now();
+        COMMIT TRANSACTION;
         "#;
 
-        self.db.query(sql).bind(("category", category_id)).await?;
+        self.db
+            .query(sql)
+            .bind(("category", category_id))
+            .await?
+            .check()?;
 
         Ok(())
     }
 
+    /// Brings the category back from the trash under `parent_id`.
+    pub async fn restore(
+        &self,
+        category_id: RecordId,
+        parent_id: Option<RecordId>,
+    ) -> Result<(), DbError> {
+        let sql = r#"
+        BEGIN TRANSACTION;
+        UPDATE transaction SET
+            deleted_at = NONE,
+            trashed_with = NONE
+        WHERE trashed_with = $category;
+        UPDATE ONLY $category SET deleted_at = NONE, parent = $parent;
+        COMMIT TRANSACTION;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("category", category_id))
+            .bind(("parent", parent_id))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    pub async fn list_trashed(&self, user_id: RecordId) -> Result<Vec<TrashedCategory>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            name,
+            icon,
+            deleted_at
+        FROM $user->user_category.out
+        WHERE deleted_at != NONE
+        ORDER BY deleted_at DESC;
+        "#;
+
+        Ok(self.db.query(sql).bind(("user", user_id)).await?.take(0)?)
+    }
+
+    /// Folds `source` into `target`: its transactions, split shares, live
+    /// subcategories and everything filed under it move over before `source`
+    /// itself is deleted. Trashed subcategories are left at the top level.
+    /// Transactions are moved with `fn::move_transaction`, whose detached
+    /// edges keep the `category_transaction` event from taking the
+    /// transactions down with the old edge.
+    pub async fn merge(&self, source_id: RecordId, target_id: RecordId) -> Result<(), DbError> {
+        let sql = r#"
+        BEGIN TRANSACTION;
+        FOR $transaction IN $source<-user_category->category_transaction.out {
+            fn::move_transaction($transaction, $target);
+        };
+        FOR $split IN (SELECT id, in, amount FROM $source<-transaction_split) {
+            LET $existing = (
+                SELECT VALUE id
+                FROM transaction_split
+                WHERE in = $split.in AND out = $target
+            );
+            IF array::len($existing) > 0 {
+                UPDATE $existing SET amount += $split.amount;
+            } ELSE {
+                LET $transaction = $split.in;
+                RELATE $transaction -> transaction_split -> $target SET amount = $split.amount;
+            };
+            DELETE $split.id;
+        };
+        UPDATE category SET parent = $target WHERE parent = $source AND deleted_at = NONE;
+        UPDATE category SET parent = NONE WHERE parent = $source;
+        UPDATE installment_plan SET category = $target WHERE category = $source;
+        UPDATE recurring SET category = $target WHERE category = $source;
+        UPDATE rule SET category = $target WHERE category = $source;
+        UPDATE template SET category = $target WHERE category = $source;
+        UPDATE transaction SET trashed_with = $target WHERE trashed_with = $source;
+        DELETE $source;
+        COMMIT TRANSACTION;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("source", source_id))
+            .bind(("target", target_id))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    /// Lists the category's live subcategories.
+    pub async fn children(&self, category_id: RecordId) -> Result<Vec<CategoryName>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            name,
+            parent
+        FROM category
+        WHERE parent = $category AND deleted_at = NONE;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("category", category_id))
+            .await?
+            .take(0)?)
+    }
+
+    /// Permanently deletes categories trashed before `before`, together with
+    /// all of their transactions.
+    pub async fn purge_trashed(&self, before: Datetime) -> Result<(), DbError> {
+        // Transactions split into a purged category fall back to being unsplit
+        // so their remaining splits don't leave part of the amount unattributed.
+        let sql = r#"
+        FOR $category IN (
+            SELECT VALUE id
+            FROM category
+            WHERE deleted_at != NONE AND deleted_at < $before
+        ) {
+            LET $split = (SELECT VALUE in FROM $category<-transaction_split);
+            DELETE transaction_split WHERE in IN $split;
+            DELETE (SELECT VALUE id FROM $category<-user_category->category_transaction);
+            DELETE ONLY $category RETURN BEFORE;
+        };
+        "#;
+
+        self.db.query(sql).bind(("before", before)).await?;
+
+        Ok(())
+    }
+
+    /// Refunds and reimbursements are netted against the totals they fall
+    /// under, so a returned purchase no longer inflates its category. Pending
+    /// transactions are left out unless `include_pending` is set. Categories
+    /// come back as a tree, each with its own and its rolled-up totals.
     pub async fn get_expenses_overview(
         &self,
         user_id: RecordId,
         start: Datetime,
         end: Datetime,
+        tag_id: Option<RecordId>,
+        include_pending: bool,
+        tz: Tz,
     ) -> Result<ExpensesOverview, DbError> {
         let sql = r#"
         SELECT
-            time::format(date, "%Y-%m-%d") AS date,
-            math::sum(amount) AS amount
+            date,
+            IF refund_of THEN -amount ELSE amount END AS amount
         FROM $user->user_category->category_transaction.out
-        WHERE created_at IN $start..=$end
-        GROUP BY date
-        ORDER BY date NUMERIC;
+        WHERE
+            date IN $start..=$end
+            AND deleted_at = NONE
+            AND ($pending OR fn::transaction_status(status, date) != 'pending')
+            AND (!$tag OR $tag IN ->transaction_tag.out);
         SELECT
             *,
             count(raw_transactions) AS transactions,
@@ -154,14 +451,52 @@ impl<'a> CategoryRepo<'a> {
         FROM (
             SELECT
                 id,
+                parent,
                 name,
                 icon,
-                (
-                    SELECT VALUE amount
-                    FROM <-user_category->category_transaction.out
-                    WHERE date IN $start..=$end
+                array::concat(
+                    (
+                        SELECT VALUE IF refund_of THEN -amount ELSE amount END
+                        FROM <-user_category->category_transaction.out
+                        WHERE
+                            date IN $start..=$end
+                            AND deleted_at = NONE
+                            AND ($pending OR fn::transaction_status(status, date) != 'pending')
+                            AND array::len(->transaction_split) = 0
+                            AND (!$tag OR $tag IN ->transaction_tag.out)
+                    ),
+                    (
+                        SELECT VALUE IF in.refund_of THEN -amount ELSE amount END
+                        FROM <-transaction_split
+                        WHERE
+                            in.date IN $start..=$end
+                            AND in.deleted_at = NONE
+                            AND ($pending OR fn::transaction_status(in.status, in.date) != 'pending')
+                            AND (!$tag OR $tag IN in->transaction_tag.out)
+                    )
                 ) AS raw_transactions
             FROM $user->user_category.out
+            WHERE deleted_at = NONE
+        );
+        SELECT
+            *,
+            count(raw_transactions) AS transactions,
+            math::sum(raw_transactions) AS amount
+        OMIT raw_transactions
+        FROM (
+            SELECT
+                id,
+                name,
+                (
+                    SELECT VALUE IF refund_of THEN -amount ELSE amount END
+                    FROM <-transaction_tag.in
+                    WHERE
+                        date IN $start..=$end
+                        AND deleted_at = NONE
+                        AND ($pending OR fn::transaction_status(status, date) != 'pending')
+                ) AS raw_transactions
+            FROM $user->user_tag.out
+            WHERE !$tag OR id = $tag
         );
         "#;
 
@@ -171,11 +506,36 @@ impl<'a> CategoryRepo<'a> {
             .bind(("user", user_id))
             .bind(("start", start))
             .bind(("end", end))
+            .bind(("tag", tag_id))
+            .bind(("pending", include_pending))
             .await?;
 
+        #[derive(Deserialize)]
+        struct Entry {
+            date: Datetime,
+            amount: f64,
+        }
+
+        // Days are bucketed here rather than in the query so they follow the
+        // user's calendar instead of UTC's.
+        let mut daily = BTreeMap::<NaiveDate, f64>::new();
+
+        for entry in res.take::<Vec<Entry>>(0)? {
+            let day = to_utc(&entry.date).with_timezone(&tz).date_naive();
+
+            *daily.entry(day).or_default() += entry.amount;
+        }
+
         Ok(ExpensesOverview {
-            daily_expense: res.take::<Vec<Expense>>(0)?,
-            categories: res.take::<Vec<Category>>(1)?,
+            daily_expense: daily
+                .into_iter()
+                .map(|(date, amount)| Expense {
+                    date: date.format("%Y-%m-%d").to_string(),
+                    amount,
+                })
+                .collect(),
+            categories: category_tree::build(res.take::<Vec<Category>>(1)?),
+            tags: res.take::<Vec<TagTotal>>(2)?,
         })
     }
 }
@@ -0,0 +1,157 @@
+use serde_json::json;
+use surrealdb::RecordId;
+
+use crate::{
+    db::{ApiDb, DbError},
+    models::Comment,
+};
+
+pub struct CommentRepo<'a> {
+    db: &'a ApiDb,
+}
+
+impl<'a> CommentRepo<'a> {
+    pub fn new(db: &'a ApiDb) -> Self {
+        Self { db }
+    }
+
+    /// Whether `comment_id` was left on `transaction_id`.
+    pub async fn belongs_to(
+        &self,
+        transaction_id: RecordId,
+        comment_id: RecordId,
+    ) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $comment
+            WHERE transaction = $transaction
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("transaction", transaction_id))
+            .bind(("comment", comment_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn is_author(
+        &self,
+        user_id: RecordId,
+        comment_id: RecordId,
+    ) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $comment
+            WHERE author = $user
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("comment", comment_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn create(
+        &self,
+        transaction_id: RecordId,
+        user_id: RecordId,
+        parent_id: Option<RecordId>,
+        body: String,
+    ) -> Result<RecordId, DbError> {
+        let sql = "fn::add_comment($transaction, $user, $parent, $body);";
+
+        self.db
+            .query(sql)
+            .bind(("transaction", transaction_id))
+            .bind(("user", user_id))
+            .bind(("parent", parent_id))
+            .bind(("body", body))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotCreated("transaction_comment".into()))
+    }
+
+    pub async fn edit(&self, id: RecordId, body: String) -> Result<(), DbError> {
+        let sql = "UPDATE ONLY $comment SET body = $body;";
+
+        self.db
+            .query(sql)
+            .bind(("comment", id))
+            .bind(("body", body))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Deletes the comment along with its replies.
+    pub async fn delete(&self, id: RecordId) -> Result<(), DbError> {
+        let sql = "DELETE ONLY $comment;";
+
+        self.db.query(sql).bind(("comment", id)).await?;
+
+        Ok(())
+    }
+
+    pub async fn get(&self, id: RecordId) -> Result<Comment, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            parent,
+            author,
+            author.username AS username,
+            body,
+            created_at,
+            updated_at
+        FROM ONLY $comment;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("comment", id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotFound(
+                json!({"comment": "No comment found with that id"}),
+            ))
+    }
+
+    /// Lists the transaction's comments oldest first, so that replies always
+    /// follow what they answer.
+    pub async fn list(&self, transaction_id: RecordId) -> Result<Vec<Comment>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            parent,
+            author,
+            author.username AS username,
+            body,
+            created_at,
+            updated_at
+        FROM transaction_comment
+        WHERE transaction = $transaction
+        ORDER BY created_at;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("transaction", transaction_id))
+            .await?
+            .take(0)?)
+    }
+}
@@ -0,0 +1,96 @@
+use surrealdb::RecordId;
+
+use crate::db::{ApiDb, DbError};
+
+pub struct DuplicateRepo<'a> {
+    db: &'a ApiDb,
+}
+
+impl<'a> DuplicateRepo<'a> {
+    pub fn new(db: &'a ApiDb) -> Self {
+        Self { db }
+    }
+
+    /// Returns the dismissed pairs involving any of `transaction_ids`.
+    pub async fn dismissed(
+        &self,
+        transaction_ids: Vec<RecordId>,
+    ) -> Result<Vec<Vec<RecordId>>, DbError> {
+        let sql = r#"
+        SELECT VALUE transactions
+        FROM duplicate_dismissal
+        WHERE transactions ANYINSIDE $transactions;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("transactions", transaction_ids))
+            .await?
+            .take(0)?)
+    }
+
+    pub async fn dismiss(&self, first: RecordId, second: RecordId) -> Result<(), DbError> {
+        let sql = r#"
+        IF (
+            SELECT VALUE id
+            FROM duplicate_dismissal
+            WHERE transactions CONTAINSALL [$first, $second]
+            LIMIT 1
+        ) = [] {
+            CREATE duplicate_dismissal SET transactions = [$first, $second];
+        };
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("first", first))
+            .bind(("second", second))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Folds `duplicate` into `original`: its attachments, comments, refunds
+    /// and tags move over, its note fills in a missing one, and it is then
+    /// trashed. Changes to `original` are recorded as a revision by `user_id`.
+    pub async fn merge(
+        &self,
+        user_id: RecordId,
+        original: RecordId,
+        duplicate: RecordId,
+    ) -> Result<(), DbError> {
+        let sql = r#"
+        BEGIN TRANSACTION;
+        LET $before = fn::transaction_snapshot($original);
+        UPDATE attachment SET transaction = $original WHERE transaction = $duplicate;
+        UPDATE transaction_comment SET transaction = $original WHERE transaction = $duplicate;
+        UPDATE transaction SET refund_of = $original WHERE refund_of = $duplicate AND id != $original;
+        FOR $tag IN array::complement($duplicate->transaction_tag.out, $original->transaction_tag.out) {
+            RELATE $original -> transaction_tag -> $tag;
+        };
+        LET $note = $duplicate.note;
+        UPDATE ONLY $original SET note = note ?? $note;
+        UPDATE ONLY $duplicate SET deleted_at = time::now();
+        LET $after = fn::transaction_snapshot($original);
+        IF $before != $after {
+            CREATE transaction_revision SET
+                transaction = $original,
+                user = $user,
+                before = $before,
+                after = $after;
+        };
+        COMMIT TRANSACTION;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("original", original))
+            .bind(("duplicate", duplicate))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+}
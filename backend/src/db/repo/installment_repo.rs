@@ -0,0 +1,183 @@
+use serde_json::json;
+use surrealdb::{Datetime, RecordId};
+
+use crate::{
+    db::{ApiDb, DbError},
+    models::InstallmentPlan,
+};
+
+pub struct InstallmentRepo<'a> {
+    db: &'a ApiDb,
+}
+
+impl<'a> InstallmentRepo<'a> {
+    pub fn new(db: &'a ApiDb) -> Self {
+        Self { db }
+    }
+
+    pub async fn user_owns(&self, user_id: RecordId, plan_id: RecordId) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY installment_plan
+            WHERE
+                id = $plan
+                AND category IN $user->user_category.out
+                AND category.deleted_at = NONE
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("plan", plan_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn create(
+        &self,
+        category_id: RecordId,
+        account_id: Option<RecordId>,
+        note: Option<String>,
+        total: f64,
+        count: u32,
+        start: Datetime,
+    ) -> Result<RecordId, DbError> {
+        let sql = r#"
+        CREATE installment_plan SET
+            category = $category,
+            account = $account,
+            note = $note,
+            total = $total,
+            count = $count,
+            start = $start
+        RETURN VALUE id;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("category", category_id))
+            .bind(("account", account_id))
+            .bind(("note", note))
+            .bind(("total", total))
+            .bind(("count", count))
+            .bind(("start", start))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotCreated("installment_plan".into()))
+    }
+
+    /// Marks `transaction_id` as the `number`th (one-based) installment of
+    /// `plan_id`.
+    pub async fn link(
+        &self,
+        plan_id: RecordId,
+        transaction_id: RecordId,
+        number: u32,
+    ) -> Result<(), DbError> {
+        let sql = r#"
+        UPDATE ONLY $transaction SET
+            installment = $plan,
+            installment_number = $number;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("plan", plan_id))
+            .bind(("transaction", transaction_id))
+            .bind(("number", number))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Permanently deletes a plan whose creation failed, together with the
+    /// `transactions` already created for it.
+    pub async fn discard(&self, id: RecordId, transactions: Vec<RecordId>) -> Result<(), DbError> {
+        let sql = r#"
+        BEGIN TRANSACTION;
+        DELETE category_transaction WHERE out IN $transactions;
+        DELETE ONLY $plan RETURN BEFORE;
+        COMMIT TRANSACTION;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("plan", id))
+            .bind(("transactions", transactions))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    /// Lists the user's plans with what has been paid so far, counting the
+    /// installments dated up to now.
+    pub async fn list(&self, user_id: RecordId) -> Result<Vec<InstallmentPlan>, DbError> {
+        let sql = r#"
+        SELECT
+            *,
+            math::sum(paid_amounts) AS paid,
+            count(paid_amounts) AS paid_count,
+            math::sum(due_amounts) AS remaining
+        OMIT paid_amounts, due_amounts
+        FROM (
+            SELECT
+                id,
+                category,
+                account,
+                note,
+                total,
+                count,
+                start,
+                (
+                    SELECT VALUE amount
+                    FROM transaction
+                    WHERE
+                        installment = $parent.id
+                        AND deleted_at = NONE
+                        AND date <= time::now()
+                ) AS paid_amounts,
+                (
+                    SELECT VALUE amount
+                    FROM transaction
+                    WHERE
+                        installment = $parent.id
+                        AND deleted_at = NONE
+                        AND date > time::now()
+                ) AS due_amounts
+            FROM installment_plan
+            WHERE
+                category IN $user->user_category.out
+                AND category.deleted_at = NONE
+        )
+        ORDER BY start DESC;
+        "#;
+
+        Ok(self.db.query(sql).bind(("user", user_id)).await?.take(0)?)
+    }
+
+    /// Deletes the plan along with its installments that are not due yet.
+    /// Installments already paid stay behind as ordinary transactions.
+    pub async fn delete(&self, id: RecordId) -> Result<(), DbError> {
+        let sql = r#"
+        BEGIN TRANSACTION;
+        UPDATE transaction SET deleted_at = time::now()
+        WHERE
+            installment = $plan
+            AND deleted_at = NONE
+            AND date > time::now();
+        DELETE ONLY $plan RETURN BEFORE;
+        COMMIT TRANSACTION;
+        "#;
+
+        self.db.query(sql).bind(("plan", id)).await?.check()?;
+
+        Ok(())
+    }
+}
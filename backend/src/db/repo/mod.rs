@@ -1,6 +1,32 @@
+pub mod account_repo;
+pub mod attachment_repo;
 pub mod category_repo;
+pub mod comment_repo;
+pub mod duplicate_repo;
+pub mod installment_repo;
+pub mod payee_repo;
+pub mod reconciliation_repo;
+pub mod recurring_repo;
+pub mod revision_repo;
+pub mod rule_repo;
+pub mod search_repo;
+pub mod tag_repo;
+pub mod template_repo;
 pub mod transaction_repo;
 pub mod user_repo;
 
+pub use account_repo::AccountRepo;
+pub use attachment_repo::AttachmentRepo;
 pub use category_repo::CategoryRepo;
+pub use comment_repo::CommentRepo;
+pub use duplicate_repo::DuplicateRepo;
+pub use installment_repo::InstallmentRepo;
+pub use payee_repo::PayeeRepo;
+pub use reconciliation_repo::ReconciliationRepo;
+pub use recurring_repo::RecurringRepo;
+pub use revision_repo::RevisionRepo;
+pub use rule_repo::RuleRepo;
+pub use search_repo::SearchRepo;
+pub use tag_repo::TagRepo;
+pub use template_repo::TemplateRepo;
 pub use user_repo::UserRepo;
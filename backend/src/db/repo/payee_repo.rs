@@ -0,0 +1,219 @@
+use serde_json::json;
+use surrealdb::{Datetime, RecordId};
+
+use crate::{
+    db::{ApiDb, DbError},
+    models::{Payee, PayeeTotal},
+};
+
+pub struct PayeeRepo<'a> {
+    db: &'a ApiDb,
+}
+
+impl<'a> PayeeRepo<'a> {
+    pub fn new(db: &'a ApiDb) -> Self {
+        Self { db }
+    }
+
+    pub async fn exists(&self, user_id: RecordId, name: String) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $user->user_payee.out
+            WHERE string::lowercase(name) = string::lowercase($name)
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("name", name))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn exists_excluding(
+        &self,
+        user_id: RecordId,
+        name: String,
+        exclude_id: RecordId,
+    ) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $user->user_payee.out
+            WHERE
+                string::lowercase(name) = string::lowercase($name)
+                AND id != $exclude
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("name", name))
+            .bind(("exclude", exclude_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn user_owns(&self, user_id: RecordId, payee_id: RecordId) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $user->user_payee
+            WHERE out = $payee
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("payee", payee_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn create(
+        &self,
+        user_id: RecordId,
+        name: String,
+        aliases: Vec<String>,
+    ) -> Result<RecordId, DbError> {
+        let sql = "fn::add_payee($user, $name, $aliases);";
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("name", name))
+            .bind(("aliases", aliases))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotCreated("payee".into()))
+    }
+
+    pub async fn edit(
+        &self,
+        id: RecordId,
+        name: String,
+        aliases: Vec<String>,
+    ) -> Result<(), DbError> {
+        let sql = "UPDATE ONLY $payee SET name = $name, aliases = $aliases;";
+
+        self.db
+            .query(sql)
+            .bind(("payee", id))
+            .bind(("name", name))
+            .bind(("aliases", aliases))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, payee_id: RecordId) -> Result<(), DbError> {
+        let sql = r#"
+        DELETE (SELECT VALUE id FROM $payee<-user_payee);
+        DELETE ONLY $payee RETURN BEFORE;
+        "#;
+
+        self.db.query(sql).bind(("payee", payee_id)).await?;
+
+        Ok(())
+    }
+
+    pub async fn list(&self, user_id: RecordId) -> Result<Vec<Payee>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            name,
+            aliases
+        FROM $user->user_payee.out
+        ORDER BY name;
+        "#;
+
+        Ok(self.db.query(sql).bind(("user", user_id)).await?.take(0)?)
+    }
+
+    /// Case-insensitive substring search over payee names and aliases, with
+    /// names that start with the query ranked first.
+    pub async fn search(
+        &self,
+        user_id: RecordId,
+        query: String,
+        limit: usize,
+    ) -> Result<Vec<Payee>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            name,
+            aliases,
+            string::starts_with(string::lowercase(name), $query) AS prefix
+        FROM $user->user_payee.out
+        WHERE
+            string::contains(string::lowercase(name), $query)
+            OR string::contains(string::lowercase(array::join(aliases, "\n")), $query)
+        ORDER BY prefix DESC, name
+        LIMIT $limit;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("query", query.to_lowercase()))
+            .bind(("limit", limit))
+            .await?
+            .take(0)?)
+    }
+
+    pub async fn totals(
+        &self,
+        user_id: RecordId,
+        start: Datetime,
+        end: Datetime,
+    ) -> Result<Vec<PayeeTotal>, DbError> {
+        let sql = r#"
+        SELECT
+            *,
+            count(raw_transactions) AS transactions,
+            math::sum(raw_transactions) AS amount
+        OMIT raw_transactions
+        FROM (
+            SELECT
+                id,
+                name,
+                (
+                    SELECT VALUE IF refund_of THEN -amount ELSE amount END
+                    FROM transaction
+                    WHERE
+                        payee = $parent.id
+                        AND date IN $start..=$end
+                        AND deleted_at = NONE
+                ) AS raw_transactions
+            FROM $user->user_payee.out
+        )
+        ORDER BY amount DESC;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("start", start))
+            .bind(("end", end))
+            .await?
+            .take(0)?)
+    }
+}
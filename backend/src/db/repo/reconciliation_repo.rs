@@ -0,0 +1,214 @@
+use serde_json::json;
+use surrealdb::{Datetime, RecordId};
+
+use crate::{
+    db::{ApiDb, DbError},
+    models::{ReconcileItem, Reconciliation, TransactionStatus},
+};
+
+pub struct ReconciliationRepo<'a> {
+    db: &'a ApiDb,
+}
+
+impl<'a> ReconciliationRepo<'a> {
+    pub fn new(db: &'a ApiDb) -> Self {
+        Self { db }
+    }
+
+    pub async fn user_owns(
+        &self,
+        user_id: RecordId,
+        account_id: RecordId,
+        reconciliation_id: RecordId,
+    ) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY reconciliation
+            WHERE
+                id = $reconciliation
+                AND account = $account
+                AND account IN $user->user_account.out
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("account", account_id))
+            .bind(("reconciliation", reconciliation_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    /// Returns the account's unfinished reconciliation, if any.
+    pub async fn open(&self, account_id: RecordId) -> Result<Option<RecordId>, DbError> {
+        let sql = r#"
+        SELECT VALUE id
+        FROM ONLY reconciliation
+        WHERE account = $account AND finished_at = NONE
+        LIMIT 1;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("account", account_id))
+            .await?
+            .take::<Option<_>>(0)?)
+    }
+
+    pub async fn create(
+        &self,
+        account_id: RecordId,
+        statement_date: Datetime,
+        closing_balance: f64,
+    ) -> Result<RecordId, DbError> {
+        let sql = r#"
+        CREATE reconciliation SET
+            account = $account,
+            statement_date = $statement_date,
+            closing_balance = $closing_balance
+        RETURN VALUE id;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("account", account_id))
+            .bind(("statement_date", statement_date))
+            .bind(("closing_balance", closing_balance))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotCreated("reconciliation".into()))
+    }
+
+    pub async fn get(&self, id: RecordId) -> Result<Reconciliation, DbError> {
+        let sql = r#"
+        SELECT *, closing_balance - cleared_balance AS difference
+        FROM (
+            SELECT
+                id,
+                account,
+                statement_date,
+                closing_balance,
+                finished_at,
+                account.opening_balance
+                    - math::sum((
+                        SELECT VALUE IF refund_of THEN -amount ELSE amount END
+                        FROM transaction
+                        WHERE
+                            account = $parent.account
+                            AND date <= $parent.statement_date
+                            AND deleted_at = NONE
+                            AND fn::transaction_status(status, date) != 'pending'
+                    ))
+                    - math::sum((
+                        SELECT VALUE amount
+                        FROM transfer
+                        WHERE from_account = $parent.account AND date <= $parent.statement_date
+                    ))
+                    + math::sum((
+                        SELECT VALUE amount
+                        FROM transfer
+                        WHERE to_account = $parent.account AND date <= $parent.statement_date
+                    ))
+                    AS cleared_balance
+            FROM ONLY $reconciliation
+        );
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("reconciliation", id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotFound(
+                json!({"reconciliation": "No reconciliation found with that id"}),
+            ))
+    }
+
+    /// Lists the transactions on the account up to the statement date that
+    /// have not been reconciled yet, oldest first.
+    pub async fn items(&self, id: RecordId) -> Result<Vec<ReconcileItem>, DbError> {
+        let sql = r#"
+        LET $reconciliation = (SELECT * FROM ONLY $id);
+        SELECT
+            id,
+            IF refund_of THEN -amount ELSE amount END AS amount,
+            note,
+            date,
+            fn::transaction_status(status, date) AS status
+        FROM transaction
+        WHERE
+            account = $reconciliation.account
+            AND date <= $reconciliation.statement_date
+            AND deleted_at = NONE
+            AND status != 'reconciled'
+        ORDER BY date;
+        "#;
+
+        Ok(self.db.query(sql).bind(("id", id)).await?.take(1)?)
+    }
+
+    /// Sets the status of those of `transactions` that are on the account
+    /// and not reconciled yet.
+    pub async fn mark(
+        &self,
+        account_id: RecordId,
+        transactions: Vec<RecordId>,
+        status: TransactionStatus,
+    ) -> Result<(), DbError> {
+        let sql = r#"
+        UPDATE transaction SET status = $status
+        WHERE
+            id IN $transactions
+            AND account = $account
+            AND deleted_at = NONE
+            AND status != 'reconciled';
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("account", account_id))
+            .bind(("transactions", transactions))
+            .bind(("status", status))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Locks every cleared transaction up to the statement date as reconciled
+    /// by this session and closes it.
+    pub async fn finish(&self, id: RecordId) -> Result<(), DbError> {
+        let sql = r#"
+        BEGIN TRANSACTION;
+        LET $reconciliation = (SELECT * FROM ONLY $id);
+        UPDATE transaction SET
+            status = 'reconciled',
+            reconciliation = $id
+        WHERE
+            account = $reconciliation.account
+            AND date <= $reconciliation.statement_date
+            AND deleted_at = NONE
+            AND fn::transaction_status(status, date) = 'cleared';
+        UPDATE ONLY $id SET finished_at = time::now();
+        COMMIT TRANSACTION;
+        "#;
+
+        self.db.query(sql).bind(("id", id)).await?.check()?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, id: RecordId) -> Result<(), DbError> {
+        let sql = "DELETE ONLY $reconciliation RETURN BEFORE;";
+
+        self.db.query(sql).bind(("reconciliation", id)).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+use serde_json::json;
+use surrealdb::{Datetime, RecordId};
+
+use crate::{
+    db::{
+        ApiDb, DbError,
+        repo::transaction_repo::{ADD_TRANSACTION, bind_draft},
+    },
+    models::{Recurring, RecurringSchedule, TransactionDraft},
+};
+
+pub struct RecurringRepo<'a> {
+    db: &'a ApiDb,
+}
+
+impl<'a> RecurringRepo<'a> {
+    pub fn new(db: &'a ApiDb) -> Self {
+        Self { db }
+    }
+
+    pub async fn user_owns(
+        &self,
+        user_id: RecordId,
+        recurring_id: RecordId,
+    ) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY recurring
+            WHERE
+                id = $recurring
+                AND category IN $user->user_category.out
+                AND category.deleted_at = NONE
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("recurring", recurring_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn create(
+        &self,
+        category_id: RecordId,
+        account_id: Option<RecordId>,
+        amount: f64,
+        note: Option<String>,
+        schedule: RecurringSchedule,
+        next_date: Option<Datetime>,
+    ) -> Result<RecordId, DbError> {
+        let sql = r#"
+        CREATE recurring SET
+            category = $category,
+            account = $account,
+            amount = $amount,
+            note = $note,
+            schedule = $schedule,
+            next_date = $next_date
+        RETURN VALUE id;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("category", category_id))
+            .bind(("account", account_id))
+            .bind(("amount", amount))
+            .bind(("note", note))
+            .bind(("schedule", schedule))
+            .bind(("next_date", next_date))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotCreated("recurring".into()))
+    }
+
+    pub async fn get(&self, id: RecordId) -> Result<Recurring, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            category,
+            account,
+            amount,
+            note,
+            schedule,
+            occurrences,
+            next_date,
+            stopped
+        FROM ONLY $recurring;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("recurring", id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotFound(
+                json!({"recurring": "No recurring transaction found with that id"}),
+            ))
+    }
+
+    pub async fn list(&self, user_id: RecordId) -> Result<Vec<Recurring>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            category,
+            account,
+            amount,
+            note,
+            schedule,
+            occurrences,
+            next_date,
+            stopped
+        FROM recurring
+        WHERE
+            category IN $user->user_category.out
+            AND category.deleted_at = NONE
+        ORDER BY next_date;
+        "#;
+
+        Ok(self.db.query(sql).bind(("user", user_id)).await?.take(0)?)
+    }
+
+    pub async fn list_due(&self, now: Datetime) -> Result<Vec<Recurring>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            category,
+            account,
+            amount,
+            note,
+            schedule,
+            occurrences,
+            next_date,
+            stopped
+        FROM recurring
+        WHERE
+            stopped = false
+            AND category.deleted_at = NONE
+            AND next_date != NONE
+            AND next_date <= $now;
+        "#;
+
+        Ok(self.db.query(sql).bind(("now", now)).await?.take(0)?)
+    }
+
+    pub async fn skipped_dates(&self, id: RecordId) -> Result<Vec<Datetime>, DbError> {
+        let sql = r#"
+        SELECT VALUE date
+        FROM recurring_occurrence
+        WHERE recurring = $recurring AND skipped = true;
+        "#;
+
+        Ok(self.db.query(sql).bind(("recurring", id)).await?.take(0)?)
+    }
+
+    /// Claims the occurrence of `recurring` on `date` and creates its
+    /// transaction in one database transaction, so a failed create leaves the
+    /// occurrence unclaimed and it is retried on the next run. Occurrences
+    /// that were already claimed or skipped are left alone.
+    pub async fn materialize(&self, recurring: &Recurring, date: Datetime) -> Result<(), DbError> {
+        let sql = format!(
+            r#"
+        BEGIN TRANSACTION;
+        LET $occurrence = array::first(
+            INSERT IGNORE INTO recurring_occurrence {{
+                id: [$recurring, $date],
+                recurring: $recurring,
+                date: $date
+            }} RETURN VALUE id
+        );
+        IF $occurrence {{
+            LET $transaction = {ADD_TRANSACTION};
+            UPDATE ONLY $occurrence SET transaction = $transaction;
+        }};
+        COMMIT TRANSACTION;
+        "#
+        );
+
+        let draft = TransactionDraft {
+            account: recurring.account.clone(),
+            amount: recurring.amount,
+            note: recurring.note.clone(),
+            date,
+            splits: Vec::new(),
+            tags: Vec::new(),
+            payee: None,
+        };
+
+        bind_draft(self.db.query(sql), recurring.category.clone(), draft)
+            .bind(("recurring", recurring.id.clone()))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    pub async fn skip(&self, id: RecordId, date: Datetime) -> Result<bool, DbError> {
+        let sql = r#"
+        INSERT IGNORE INTO recurring_occurrence {
+            id: [$recurring, $date],
+            recurring: $recurring,
+            date: $date,
+            skipped: true
+        } RETURN VALUE id;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("recurring", id))
+            .bind(("date", date))
+            .await?
+            .take::<Option<RecordId>>(0)?
+            .is_some())
+    }
+
+    /// Moves the schedule cursor forward, guarded on `from` so that a stop or a
+    /// concurrent run in between is not overwritten.
+    pub async fn advance(
+        &self,
+        id: RecordId,
+        from: u32,
+        to: u32,
+        next_date: Option<Datetime>,
+    ) -> Result<(), DbError> {
+        let sql = r#"
+        UPDATE $recurring SET
+            occurrences = $to,
+            next_date = $next_date
+        WHERE occurrences = $from AND stopped = false;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("recurring", id))
+            .bind(("from", from))
+            .bind(("to", to))
+            .bind(("next_date", next_date))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn stop(&self, id: RecordId) -> Result<(), DbError> {
+        let sql = "UPDATE ONLY $recurring SET stopped = true, next_date = NONE;";
+
+        self.db.query(sql).bind(("recurring", id)).await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, id: RecordId) -> Result<(), DbError> {
+        let sql = "DELETE ONLY $recurring RETURN BEFORE;";
+
+        self.db.query(sql).bind(("recurring", id)).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use serde_json::json;
+use surrealdb::RecordId;
+
+use crate::{
+    db::{ApiDb, DbError},
+    models::Revision,
+};
+
+pub struct RevisionRepo<'a> {
+    db: &'a ApiDb,
+}
+
+impl<'a> RevisionRepo<'a> {
+    pub fn new(db: &'a ApiDb) -> Self {
+        Self { db }
+    }
+
+    pub async fn list(&self, transaction_id: RecordId) -> Result<Vec<Revision>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            user,
+            user.username AS username,
+            before,
+            after,
+            created_at
+        FROM transaction_revision
+        WHERE transaction = $transaction
+        ORDER BY created_at DESC;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("transaction", transaction_id))
+            .await?
+            .take(0)?)
+    }
+
+    pub async fn get(
+        &self,
+        transaction_id: RecordId,
+        revision_id: RecordId,
+    ) -> Result<Revision, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            user,
+            user.username AS username,
+            before,
+            after,
+            created_at
+        FROM ONLY $revision
+        WHERE transaction = $transaction;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("transaction", transaction_id))
+            .bind(("revision", revision_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotFound(
+                json!({"revision": "No revision found for this transaction"}),
+            ))
+    }
+}
@@ -0,0 +1,142 @@
+use serde_json::json;
+use surrealdb::RecordId;
+
+use crate::{
+    db::{ApiDb, DbError},
+    models::{Rule, RuleConditions},
+};
+
+pub struct RuleRepo<'a> {
+    db: &'a ApiDb,
+}
+
+impl<'a> RuleRepo<'a> {
+    pub fn new(db: &'a ApiDb) -> Self {
+        Self { db }
+    }
+
+    pub async fn user_owns(&self, user_id: RecordId, rule_id: RecordId) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $user->user_rule
+            WHERE out = $rule
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("rule", rule_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn create(
+        &self,
+        user_id: RecordId,
+        name: String,
+        priority: i64,
+        conditions: RuleConditions,
+        category_id: Option<RecordId>,
+        tag_ids: Vec<RecordId>,
+    ) -> Result<RecordId, DbError> {
+        let sql = "fn::add_rule($user, $name, $priority, $conditions, $category, $tags);";
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("name", name))
+            .bind(("priority", priority))
+            .bind(("conditions", conditions))
+            .bind(("category", category_id))
+            .bind(("tags", tag_ids))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotCreated("rule".into()))
+    }
+
+    pub async fn edit(
+        &self,
+        id: RecordId,
+        name: String,
+        priority: i64,
+        conditions: RuleConditions,
+        category_id: Option<RecordId>,
+        tag_ids: Vec<RecordId>,
+    ) -> Result<(), DbError> {
+        let sql = r#"
+        UPDATE ONLY $rule SET
+            name = $name,
+            priority = $priority,
+            conditions = $conditions,
+            category = $category,
+            tags = $tags;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("rule", id))
+            .bind(("name", name))
+            .bind(("priority", priority))
+            .bind(("conditions", conditions))
+            .bind(("category", category_id))
+            .bind(("tags", tag_ids))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, rule_id: RecordId) -> Result<(), DbError> {
+        let sql = r#"
+        DELETE (SELECT VALUE id FROM $rule<-user_rule);
+        DELETE ONLY $rule RETURN BEFORE;
+        "#;
+
+        self.db.query(sql).bind(("rule", rule_id)).await?;
+
+        Ok(())
+    }
+
+    /// Lists the user's rules in evaluation order: by ascending priority, then
+    /// oldest first.
+    pub async fn list(&self, user_id: RecordId) -> Result<Vec<Rule>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            name,
+            priority,
+            conditions,
+            category,
+            tags,
+            created_at
+        FROM $user->user_rule.out
+        ORDER BY priority, created_at;
+        "#;
+
+        Ok(self.db.query(sql).bind(("user", user_id)).await?.take(0)?)
+    }
+
+    /// Like `list`, leaving out rules that file into a trashed category.
+    pub async fn list_active(&self, user_id: RecordId) -> Result<Vec<Rule>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            name,
+            priority,
+            conditions,
+            category,
+            tags,
+            created_at
+        FROM $user->user_rule.out
+        WHERE category = NONE OR category.deleted_at = NONE
+        ORDER BY priority, created_at;
+        "#;
+
+        Ok(self.db.query(sql).bind(("user", user_id)).await?.take(0)?)
+    }
+}
@@ -0,0 +1,120 @@
+use surrealdb::RecordId;
+
+use crate::{
+    db::{ApiDb, DbError},
+    models::{NameHit, SearchResults, TransactionHit},
+};
+
+/// Placeholders the database wraps matches in. Highlights are built from
+/// user-entered text, so it is escaped before these become `<mark>` tags.
+const MARK_OPEN: &str = "\u{1}";
+const MARK_CLOSE: &str = "\u{2}";
+
+pub struct SearchRepo<'a> {
+    db: &'a ApiDb,
+}
+
+impl<'a> SearchRepo<'a> {
+    pub fn new(db: &'a ApiDb) -> Self {
+        Self { db }
+    }
+
+    /// Runs `query` against the full-text indexes on transaction notes, payee
+    /// names and category names. The indexes are table-wide, so every match
+    /// is narrowed down to the user's own graph before ranking.
+    pub async fn search(
+        &self,
+        user_id: RecordId,
+        query: String,
+        limit: usize,
+    ) -> Result<SearchResults, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            array::first(<-category_transaction.in.out) AS category,
+            amount,
+            note,
+            date,
+            search::highlight($open, $close, 1) AS highlight,
+            search::score(1) AS score
+        FROM transaction
+        WHERE
+            note @1@ $query
+            AND deleted_at = NONE
+            AND $user IN <-category_transaction.in<-user_category.in
+        ORDER BY score DESC
+        LIMIT $limit;
+
+        SELECT
+            id,
+            name,
+            search::highlight($open, $close, 1) AS highlight,
+            search::score(1) AS score
+        FROM payee
+        WHERE name @1@ $query AND $user IN <-user_payee.in
+        ORDER BY score DESC
+        LIMIT $limit;
+
+        SELECT
+            id,
+            name,
+            search::highlight($open, $close, 1) AS highlight,
+            search::score(1) AS score
+        FROM category
+        WHERE
+            name @1@ $query
+            AND deleted_at = NONE
+            AND $user IN <-user_category.in
+        ORDER BY score DESC
+        LIMIT $limit;
+        "#;
+
+        let mut response = self
+            .db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("query", query))
+            .bind(("limit", limit))
+            .bind(("open", MARK_OPEN))
+            .bind(("close", MARK_CLOSE))
+            .await?;
+
+        let mut transactions = response.take::<Vec<TransactionHit>>(0)?;
+        let mut payees = response.take::<Vec<NameHit>>(1)?;
+        let mut categories = response.take::<Vec<NameHit>>(2)?;
+
+        for hit in &mut transactions {
+            hit.highlight = hit.highlight.as_deref().map(mark_up);
+        }
+        for hit in payees.iter_mut().chain(&mut categories) {
+            hit.highlight = hit.highlight.as_deref().map(mark_up);
+        }
+
+        Ok(SearchResults {
+            transactions,
+            payees,
+            categories,
+        })
+    }
+}
+
+/// HTML-escapes a highlight and turns the match placeholders into `<mark>`
+/// tags.
+fn mark_up(highlight: &str) -> String {
+    let mut html = String::with_capacity(highlight.len());
+
+    for c in highlight.chars() {
+        match c {
+            '\u{1}' => html.push_str("<mark>"),
+            '\u{2}' => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+
+    html
+}
@@ -0,0 +1,155 @@
+use serde_json::json;
+use surrealdb::RecordId;
+
+use crate::{
+    db::{ApiDb, DbError},
+    models::Tag,
+};
+
+pub struct TagRepo<'a> {
+    db: &'a ApiDb,
+}
+
+impl<'a> TagRepo<'a> {
+    pub fn new(db: &'a ApiDb) -> Self {
+        Self { db }
+    }
+
+    pub async fn exists(&self, user_id: RecordId, name: String) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $user->user_tag.out
+            WHERE string::lowercase(name) = string::lowercase($name)
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("name", name))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn exists_excluding(
+        &self,
+        user_id: RecordId,
+        name: String,
+        exclude_id: RecordId,
+    ) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $user->user_tag.out
+            WHERE
+                string::lowercase(name) = string::lowercase($name)
+                AND id != $exclude
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("name", name))
+            .bind(("exclude", exclude_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn user_owns(&self, user_id: RecordId, tag_id: RecordId) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $user->user_tag
+            WHERE out = $tag
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("tag", tag_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn user_owns_all(
+        &self,
+        user_id: RecordId,
+        tag_ids: Vec<RecordId>,
+    ) -> Result<bool, DbError> {
+        let sql = r#"
+        array::len(array::complement($tags, (SELECT VALUE out FROM $user->user_tag))) = 0;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("tags", tag_ids))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn create(&self, user_id: RecordId, name: String) -> Result<RecordId, DbError> {
+        let sql = "fn::add_tag($user, $name);";
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("name", name))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotCreated("tag".into()))
+    }
+
+    pub async fn edit(&self, id: RecordId, name: String) -> Result<(), DbError> {
+        let sql = "UPDATE ONLY $tag SET name = $name;";
+
+        self.db
+            .query(sql)
+            .bind(("tag", id))
+            .bind(("name", name))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, tag_id: RecordId) -> Result<(), DbError> {
+        let sql = r#"
+        DELETE (SELECT VALUE id FROM $tag<-user_tag);
+        DELETE ONLY $tag RETURN BEFORE;
+        "#;
+
+        self.db.query(sql).bind(("tag", tag_id)).await?;
+
+        Ok(())
+    }
+
+    pub async fn list(&self, user_id: RecordId) -> Result<Vec<Tag>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            name
+        FROM $user->user_tag.out
+        ORDER BY name;
+        "#;
+
+        Ok(self.db.query(sql).bind(("user", user_id)).await?.take(0)?)
+    }
+}
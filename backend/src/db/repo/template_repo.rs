@@ -0,0 +1,195 @@
+use serde_json::json;
+use surrealdb::RecordId;
+
+use crate::{
+    db::{ApiDb, DbError},
+    models::Template,
+};
+
+pub struct TemplateRepo<'a> {
+    db: &'a ApiDb,
+}
+
+impl<'a> TemplateRepo<'a> {
+    pub fn new(db: &'a ApiDb) -> Self {
+        Self { db }
+    }
+
+    pub async fn exists(&self, user_id: RecordId, name: String) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $user->user_template.out
+            WHERE string::lowercase(name) = string::lowercase($name)
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("name", name))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn exists_excluding(
+        &self,
+        user_id: RecordId,
+        name: String,
+        exclude_id: RecordId,
+    ) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $user->user_template.out
+            WHERE
+                string::lowercase(name) = string::lowercase($name)
+                AND id != $exclude
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("name", name))
+            .bind(("exclude", exclude_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn user_owns(
+        &self,
+        user_id: RecordId,
+        template_id: RecordId,
+    ) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $user->user_template
+            WHERE out = $template
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("template", template_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    pub async fn create(
+        &self,
+        user_id: RecordId,
+        name: String,
+        category_id: RecordId,
+        amount: f64,
+        note: Option<String>,
+        tag_ids: Vec<RecordId>,
+    ) -> Result<RecordId, DbError> {
+        let sql = "fn::add_template($user, $name, $category, $amount, $note, $tags);";
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("name", name))
+            .bind(("category", category_id))
+            .bind(("amount", amount))
+            .bind(("note", note))
+            .bind(("tags", tag_ids))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotCreated("template".into()))
+    }
+
+    pub async fn edit(
+        &self,
+        id: RecordId,
+        name: String,
+        category_id: RecordId,
+        amount: f64,
+        note: Option<String>,
+        tag_ids: Vec<RecordId>,
+    ) -> Result<(), DbError> {
+        let sql = r#"
+        UPDATE ONLY $template SET
+            name = $name,
+            category = $category,
+            amount = $amount,
+            note = $note,
+            tags = $tags;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("template", id))
+            .bind(("name", name))
+            .bind(("category", category_id))
+            .bind(("amount", amount))
+            .bind(("note", note))
+            .bind(("tags", tag_ids))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, template_id: RecordId) -> Result<(), DbError> {
+        let sql = r#"
+        DELETE (SELECT VALUE id FROM $template<-user_template);
+        DELETE ONLY $template RETURN BEFORE;
+        "#;
+
+        self.db.query(sql).bind(("template", template_id)).await?;
+
+        Ok(())
+    }
+
+    pub async fn get(&self, id: RecordId) -> Result<Template, DbError> {
+        let sql = r#"
+        SELECT id, name, category, amount, note, tags, uses
+        FROM ONLY $template;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("template", id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotFound(
+                json!({"template": "No template found with that id"}),
+            ))
+    }
+
+    /// Lists the user's templates, most used first, leaving out those that
+    /// file into a trashed category.
+    pub async fn list(&self, user_id: RecordId) -> Result<Vec<Template>, DbError> {
+        let sql = r#"
+        SELECT id, name, category, amount, note, tags, uses
+        FROM $user->user_template.out
+        WHERE category.deleted_at = NONE
+        ORDER BY uses DESC, name;
+        "#;
+
+        Ok(self.db.query(sql).bind(("user", user_id)).await?.take(0)?)
+    }
+
+    pub async fn record_use(&self, id: RecordId) -> Result<(), DbError> {
+        let sql = "UPDATE ONLY $template SET uses += 1;";
+
+        self.db.query(sql).bind(("template", id)).await?;
+
+        Ok(())
+    }
+}
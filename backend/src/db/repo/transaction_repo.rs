@@ -1,11 +1,111 @@
+use serde::Serialize;
 use serde_json::json;
-use surrealdb::{Datetime, RecordId};
+use surrealdb::{Connection, Datetime, RecordId, method::Query};
 
 use crate::{
     db::{ApiDb, DbError},
-    models::Transaction,
+    models::{
+        BoundingBox, BulkAction, FrequentEntry, GeoTransaction, Location, PendingReimbursement,
+        RefundKind, Refundable, RuleTarget, Split, Transaction, TransactionDraft,
+        TransactionStatus, TransactionSummary, TrashedTransaction, UpcomingBill,
+    },
 };
 
+#[derive(Serialize)]
+struct SplitRecord {
+    category: RecordId,
+    amount: f64,
+}
+
+impl From<Split> for SplitRecord {
+    fn from(split: Split) -> Self {
+        Self {
+            category: split.category,
+            amount: split.amount,
+        }
+    }
+}
+
+fn split_records(splits: Vec<Split>) -> Vec<SplitRecord> {
+    splits.into_iter().map(SplitRecord::from).collect()
+}
+
+/// Creates a transaction under `$category` from the fields bound by
+/// `bind_draft`, for queries that create one alongside other changes.
+pub const ADD_TRANSACTION: &str =
+    "fn::add_transation($category, $account, $payee, $amount, $note, $date, $splits, $tags)";
+
+/// Binds `category_id` and the fields of `draft` that `ADD_TRANSACTION` reads.
+pub fn bind_draft<'r, C: Connection>(
+    query: Query<'r, C>,
+    category_id: RecordId,
+    draft: TransactionDraft,
+) -> Query<'r, C> {
+    query
+        .bind(("category", category_id))
+        .bind(("account", draft.account))
+        .bind(("payee", draft.payee))
+        .bind(("amount", draft.amount))
+        .bind(("note", draft.note))
+        .bind(("date", draft.date))
+        .bind(("splits", split_records(draft.splits)))
+        .bind(("tags", draft.tags))
+}
+
+#[derive(Serialize)]
+struct BulkRecord {
+    transaction: RecordId,
+    action: &'static str,
+    category: Option<RecordId>,
+    note: Option<String>,
+    tag: Option<RecordId>,
+    days: i64,
+}
+
+impl BulkRecord {
+    fn new(transaction: RecordId, action: BulkAction) -> Self {
+        let record = Self {
+            transaction,
+            action: "",
+            category: None,
+            note: None,
+            tag: None,
+            days: 0,
+        };
+
+        match action {
+            BulkAction::Delete => Self {
+                action: "delete",
+                ..record
+            },
+            BulkAction::MoveCategory(category) => Self {
+                action: "moveCategory",
+                category: Some(category),
+                ..record
+            },
+            BulkAction::SetNote(note) => Self {
+                action: "setNote",
+                note,
+                ..record
+            },
+            BulkAction::AddTag(tag) => Self {
+                action: "addTag",
+                tag: Some(tag),
+                ..record
+            },
+            BulkAction::ShiftDate(days) => Self {
+                action: "shiftDate",
+                days,
+                ..record
+            },
+        }
+    }
+}
+
+/// How far refunds may be off from the amount they pay back, to allow for
+/// rounding.
+pub const REFUND_TOLERANCE: f64 = 0.005;
+
 pub struct TransactionRepo<'a> {
     db: &'a ApiDb,
 }
@@ -24,7 +124,60 @@ impl<'a> TransactionRepo<'a> {
         (
             SELECT VALUE id
             FROM ONLY $user->user_category->category_transaction.out
-            WHERE id = $transaction
+            WHERE id = $transaction AND deleted_at = NONE
+            LIMIT 1
+        ) != NONE;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("transaction", transaction_id))
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::Unknown(json!({
+                "result": "Expected boolean got None"
+            })))
+    }
+
+    /// Returns which of `transaction_ids` the user owns, with the same rules as
+    /// `user_owns`.
+    pub async fn user_owned(
+        &self,
+        user_id: RecordId,
+        transaction_ids: Vec<RecordId>,
+    ) -> Result<Vec<RecordId>, DbError> {
+        let sql = r#"
+        SELECT VALUE id
+        FROM $user->user_category->category_transaction.out
+        WHERE id IN $transactions AND deleted_at = NONE;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("transactions", transaction_ids))
+            .await?
+            .take(0)?)
+    }
+
+    /// Whether the user owns `transaction_id` and it was trashed on its own,
+    /// rather than along with a category that is still in the trash.
+    pub async fn user_owns_trashed(
+        &self,
+        user_id: RecordId,
+        transaction_id: RecordId,
+    ) -> Result<bool, DbError> {
+        let sql = r#"
+        (
+            SELECT VALUE id
+            FROM ONLY $user->user_category->category_transaction.out
+            WHERE
+                id = $transaction
+                AND deleted_at != NONE
+                AND trashed_with = NONE
+                AND array::first(<-category_transaction.in.out).deleted_at = NONE
             LIMIT 1
         ) != NONE;
         "#;
@@ -43,73 +196,738 @@ impl<'a> TransactionRepo<'a> {
     pub async fn create(
         &self,
         category_id: RecordId,
-        amount: f64,
-        note: Option<String>,
-        date: Datetime,
+        draft: TransactionDraft,
+    ) -> Result<RecordId, DbError> {
+        bind_draft(self.db.query(ADD_TRANSACTION), category_id, draft)
+            .await?
+            .take::<Option<_>>(0)?
+            .ok_or(DbError::NotCreated("transaction".into()))
+    }
+
+    /// Creates a transaction from a bank statement, recording its `fitid` so
+    /// that the entry is recognised on re-import.
+    pub async fn create_imported(
+        &self,
+        category_id: RecordId,
+        draft: TransactionDraft,
+        fitid: Option<String>,
     ) -> Result<RecordId, DbError> {
-        let sql = "fn::add_transation($category, $amount, $note, $date);";
+        let sql = format!(
+            r#"
+        BEGIN TRANSACTION;
+        LET $transaction = {ADD_TRANSACTION};
+        UPDATE ONLY $transaction SET fitid = $fitid RETURN VALUE id;
+        COMMIT TRANSACTION;
+        "#
+        );
+
+        bind_draft(self.db.query(sql), category_id, draft)
+            .bind(("fitid", fitid))
+            .await?
+            .check()?
+            .take::<Option<_>>(1)?
+            .ok_or(DbError::NotCreated("transaction".into()))
+    }
+
+    /// Creates a refund or reimbursement of `original` under `category_id`.
+    pub async fn create_refund(
+        &self,
+        category_id: RecordId,
+        original: RecordId,
+        kind: RefundKind,
+        draft: TransactionDraft,
+    ) -> Result<RecordId, DbError> {
+        let sql = format!(
+            r#"
+        BEGIN TRANSACTION;
+        LET $transaction = {ADD_TRANSACTION};
+        UPDATE ONLY $transaction SET
+            refund_of = $original,
+            refund_kind = $kind
+        RETURN VALUE id;
+        COMMIT TRANSACTION;
+        "#
+        );
+
+        bind_draft(self.db.query(sql), category_id, draft)
+            .bind(("original", original))
+            .bind(("kind", kind))
+            .await?
+            .check()?
+            .take::<Option<_>>(1)?
+            .ok_or(DbError::NotCreated("transaction".into()))
+    }
+
+    /// Returns what a refund of `id` is built from. `refunded` totals the live
+    /// refunds of it other than `exclude`.
+    pub async fn refundable(
+        &self,
+        id: RecordId,
+        exclude: Option<RecordId>,
+    ) -> Result<Refundable, DbError> {
+        let sql = r#"
+        SELECT
+            amount,
+            array::first(<-category_transaction.in.out) AS category,
+            account,
+            payee,
+            (
+                SELECT out AS category, amount
+                FROM transaction_split
+                WHERE in = $parent.id
+            ) AS splits,
+            ->transaction_tag.out AS tags,
+            refund_of,
+            math::sum((
+                SELECT VALUE amount
+                FROM transaction
+                WHERE
+                    refund_of = $parent.id
+                    AND deleted_at = NONE
+                    AND id != $exclude
+            )) AS refunded
+        FROM ONLY $transaction;
+        "#;
 
         self.db
             .query(sql)
-            .bind(("category", category_id))
-            .bind(("amount", amount))
-            .bind(("note", note))
-            .bind(("date", date))
+            .bind(("transaction", id))
+            .bind(("exclude", exclude))
             .await?
             .take::<Option<_>>(0)?
-            .ok_or(DbError::NotCreated("transaction".into()))
+            .ok_or(DbError::NotFound(
+                json!({"transaction": "No transaction found with that id"}),
+            ))
     }
 
-    pub async fn edit(
+    /// Lists the live refunds and reimbursements of `id`, oldest first.
+    pub async fn refunds(&self, id: RecordId) -> Result<Vec<Transaction>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            amount,
+            note,
+            date,
+            account,
+            payee,
+            refund_of,
+            refund_kind,
+            IF location THEN {
+                lat: location.coordinates[1],
+                lon: location.coordinates[0],
+                place: place
+            } END AS location,
+            ->transaction_tag.out AS tags,
+            (
+                SELECT out AS category, amount
+                FROM transaction_split
+                WHERE in = $parent.id
+            ) AS splits
+        FROM transaction
+        WHERE refund_of = $transaction AND deleted_at = NONE
+        ORDER BY date;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("transaction", id))
+            .await?
+            .take(0)?)
+    }
+
+    /// Returns which of `transaction_ids` are locked by a finished
+    /// reconciliation.
+    pub async fn reconciled(
+        &self,
+        transaction_ids: Vec<RecordId>,
+    ) -> Result<Vec<RecordId>, DbError> {
+        let sql = r#"
+        SELECT VALUE id
+        FROM transaction
+        WHERE id IN $transactions AND status = 'reconciled';
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("transactions", transaction_ids))
+            .await?
+            .take(0)?)
+    }
+
+    pub async fn set_status(&self, id: RecordId, status: TransactionStatus) -> Result<(), DbError> {
+        let sql = "UPDATE ONLY $transaction SET status = $status;";
+
+        self.db
+            .query(sql)
+            .bind(("transaction", id))
+            .bind(("status", status))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Sets or clears where the transaction happened.
+    pub async fn set_location(
         &self,
         id: RecordId,
-        amount: f64,
-        note: Option<String>,
-        date: Datetime,
+        location: Option<Location>,
     ) -> Result<(), DbError> {
-        let sql = "UPDATE ONLY $transaction SET amount = $amount, note = $note, date = $date;";
+        let sql = r#"
+        UPDATE ONLY $transaction SET
+            location = IF $location THEN <point> [$location.lon, $location.lat] END,
+            place = $location.place;
+        "#;
 
         self.db
             .query(sql)
             .bind(("transaction", id))
-            .bind(("amount", amount))
-            .bind(("note", note))
-            .bind(("date", date))
+            .bind(("location", location))
             .await?;
 
         Ok(())
     }
 
+    /// Lists the user's located transactions dated within `start..=end`,
+    /// optionally only those inside `bbox`. Refunds count as negative
+    /// amounts.
+    pub async fn located(
+        &self,
+        user_id: RecordId,
+        start: Datetime,
+        end: Datetime,
+        bbox: Option<BoundingBox>,
+        include_pending: bool,
+    ) -> Result<Vec<GeoTransaction>, DbError> {
+        // A box whose west edge lies east of its east edge crosses the
+        // antimeridian.
+        let sql = r#"
+        SELECT
+            id,
+            array::first(<-category_transaction.in.out) AS category,
+            IF refund_of THEN -amount ELSE amount END AS amount,
+            note,
+            date,
+            place,
+            location.coordinates[1] AS lat,
+            location.coordinates[0] AS lon
+        FROM $user->user_category->category_transaction.out
+        WHERE
+            location != NONE
+            AND date IN $start..=$end
+            AND deleted_at = NONE
+            AND ($pending OR fn::transaction_status(status, date) != 'pending')
+            AND (
+                !$bbox
+                OR (
+                    location.coordinates[1] >= $bbox.south
+                    AND location.coordinates[1] <= $bbox.north
+                    AND IF $bbox.west <= $bbox.east THEN
+                        location.coordinates[0] >= $bbox.west
+                        AND location.coordinates[0] <= $bbox.east
+                    ELSE
+                        location.coordinates[0] >= $bbox.west
+                        OR location.coordinates[0] <= $bbox.east
+                    END
+                )
+            )
+        ORDER BY date;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("start", start))
+            .bind(("end", end))
+            .bind(("bbox", bbox))
+            .bind(("pending", include_pending))
+            .await?
+            .take(0)?)
+    }
+
+    /// Lists the user's pending transactions dated up to `end`, including
+    /// overdue ones, oldest first.
+    pub async fn upcoming(
+        &self,
+        user_id: RecordId,
+        end: Datetime,
+    ) -> Result<Vec<UpcomingBill>, DbError> {
+        let sql = r#"
+        SELECT
+            out AS transaction,
+            in.out AS category,
+            out.amount AS amount,
+            out.note AS note,
+            out.date AS date
+        FROM $user->user_category->category_transaction
+        WHERE
+            fn::transaction_status(out.status, out.date) = 'pending'
+            AND out.date <= $end
+            AND out.deleted_at = NONE
+            AND in.out.deleted_at = NONE
+        ORDER BY date;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("end", end))
+            .await?
+            .take(0)?)
+    }
+
+    pub async fn set_reimbursable(&self, id: RecordId, reimbursable: bool) -> Result<(), DbError> {
+        let sql = r#"
+        UPDATE ONLY $transaction SET reimbursable = $reimbursable;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("transaction", id))
+            .bind(("reimbursable", reimbursable))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Lists the user's reimbursable expenses that have not been fully paid
+    /// back yet, oldest first. Refunds count towards the repayment too.
+    pub async fn pending_reimbursements(
+        &self,
+        user_id: RecordId,
+    ) -> Result<Vec<PendingReimbursement>, DbError> {
+        let sql = r#"
+        SELECT *, amount - repaid AS outstanding
+        FROM (
+            SELECT
+                out AS id,
+                in.out AS category,
+                out.amount AS amount,
+                out.note AS note,
+                out.date AS date,
+                math::sum((
+                    SELECT VALUE amount
+                    FROM transaction
+                    WHERE refund_of = $parent.out AND deleted_at = NONE
+                )) AS repaid
+            FROM $user->user_category->category_transaction
+            WHERE out.reimbursable = true AND out.deleted_at = NONE
+        )
+        WHERE amount - repaid > $tolerance
+        ORDER BY date;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("tolerance", REFUND_TOLERANCE))
+            .await?
+            .take(0)?)
+    }
+
+    /// Returns which of `fitids` the user has already imported.
+    pub async fn imported_fitids(
+        &self,
+        user_id: RecordId,
+        fitids: Vec<String>,
+    ) -> Result<Vec<String>, DbError> {
+        let sql = r#"
+        SELECT VALUE fitid
+        FROM transaction
+        WHERE
+            fitid IN $fitids
+            AND $user IN <-category_transaction.in.in;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("fitids", fitids))
+            .await?
+            .take(0)?)
+    }
+
+    /// Updates the transaction, recording the change as a revision by
+    /// `user_id` unless nothing actually changed. `status` is only set when
+    /// given, and `location` only replaced when given (`Some(None)` clears
+    /// it), all within the same database transaction.
+    pub async fn edit(
+        &self,
+        id: RecordId,
+        user_id: RecordId,
+        draft: TransactionDraft,
+        status: Option<TransactionStatus>,
+        location: Option<Option<Location>>,
+    ) -> Result<(), DbError> {
+        let sql = r#"
+        BEGIN TRANSACTION;
+        LET $before = fn::transaction_snapshot($transaction);
+        UPDATE ONLY $transaction SET
+            account = $account,
+            payee = $payee,
+            amount = $amount,
+            note = $note,
+            date = $date;
+        fn::set_splits($transaction, $splits);
+        fn::set_tags($transaction, $tags);
+        IF $status {
+            UPDATE ONLY $transaction SET status = $status;
+        } ELSE IF $before.date != $date {
+            UPDATE ONLY $transaction SET status = NONE WHERE status != 'reconciled';
+        };
+        IF $set_location {
+            UPDATE ONLY $transaction SET
+                location = IF $location THEN <point> [$location.lon, $location.lat] END,
+                place = $location.place;
+        };
+        LET $after = fn::transaction_snapshot($transaction);
+        IF $before != $after {
+            CREATE transaction_revision SET
+                transaction = $transaction,
+                user = $user,
+                before = $before,
+                after = $after;
+        };
+        COMMIT TRANSACTION;
+        "#;
+
+        self.db
+            .query(sql)
+            .bind(("transaction", id))
+            .bind(("user", user_id))
+            .bind(("account", draft.account))
+            .bind(("payee", draft.payee))
+            .bind(("amount", draft.amount))
+            .bind(("note", draft.note))
+            .bind(("date", draft.date))
+            .bind(("splits", split_records(draft.splits)))
+            .bind(("tags", draft.tags))
+            .bind(("status", status))
+            .bind(("set_location", location.is_some()))
+            .bind(("location", location.flatten()))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    /// Applies `operations` in order within a single database transaction, so
+    /// either all of them take effect or none do. Edits are recorded as
+    /// revisions by `user_id`, like `edit` does.
+    pub async fn bulk(
+        &self,
+        user_id: RecordId,
+        operations: Vec<(RecordId, BulkAction)>,
+    ) -> Result<(), DbError> {
+        let sql = r#"
+        BEGIN TRANSACTION;
+        FOR $operation IN $operations {
+            LET $transaction = $operation.transaction;
+            LET $before = fn::transaction_snapshot($transaction);
+            IF $operation.action = "delete" {
+                UPDATE ONLY $transaction SET deleted_at = time::now();
+            } ELSE IF $operation.action = "moveCategory" {
+                fn::move_transaction($transaction, $operation.category);
+            } ELSE IF $operation.action = "setNote" {
+                UPDATE ONLY $transaction SET note = $operation.note;
+            } ELSE IF $operation.action = "addTag" {
+                LET $tag = $operation.tag;
+                IF $tag NOT IN $transaction->transaction_tag.out {
+                    RELATE $transaction -> transaction_tag -> $tag;
+                };
+            } ELSE IF $operation.action = "shiftDate" {
+                IF $operation.days >= 0 {
+                    UPDATE ONLY $transaction SET date = date + duration::from::days($operation.days);
+                } ELSE {
+                    UPDATE ONLY $transaction SET date = date - duration::from::days(-$operation.days);
+                };
+                UPDATE ONLY $transaction SET status = NONE WHERE status != 'reconciled';
+            };
+            LET $after = fn::transaction_snapshot($transaction);
+            IF $operation.action != "delete" AND $before != $after {
+                CREATE transaction_revision SET
+                    transaction = $transaction,
+                    user = $user,
+                    before = $before,
+                    after = $after;
+            };
+        };
+        COMMIT TRANSACTION;
+        "#;
+
+        let operations = operations
+            .into_iter()
+            .map(|(transaction, action)| BulkRecord::new(transaction, action))
+            .collect::<Vec<_>>();
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("operations", operations))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
     pub async fn delete(&self, id: RecordId) -> Result<(), DbError> {
-        let sql = "DELETE ONLY $transaction RETURN BEFORE;";
+        let sql = "UPDATE ONLY $transaction SET deleted_at = time::now();";
+
+        self.db.query(sql).bind(("transaction", id)).await?;
+
+        Ok(())
+    }
+
+    pub async fn restore(&self, id: RecordId) -> Result<(), DbError> {
+        let sql = "UPDATE ONLY $transaction SET deleted_at = NONE;";
 
         self.db.query(sql).bind(("transaction", id)).await?;
 
         Ok(())
     }
 
+    /// Lists the transactions the user trashed on their own; those trashed
+    /// along with a category are restored through it.
+    pub async fn list_trashed(
+        &self,
+        user_id: RecordId,
+    ) -> Result<Vec<TrashedTransaction>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            array::first(<-category_transaction.in.out) AS category,
+            amount,
+            note,
+            date,
+            deleted_at
+        FROM $user->user_category->category_transaction.out
+        WHERE
+            deleted_at != NONE
+            AND trashed_with = NONE
+            AND array::first(<-category_transaction.in.out).deleted_at = NONE
+        ORDER BY deleted_at DESC;
+        "#;
+
+        Ok(self.db.query(sql).bind(("user", user_id)).await?.take(0)?)
+    }
+
+    /// Permanently deletes transactions trashed before `before`. Removing the
+    /// `category_transaction` edge takes the transaction and its attachments
+    /// with it.
+    pub async fn purge_trashed(&self, before: Datetime) -> Result<(), DbError> {
+        let sql = r#"
+        LET $trashed = (
+            SELECT VALUE id
+            FROM transaction
+            WHERE deleted_at != NONE AND deleted_at < $before
+        );
+        DELETE category_transaction WHERE out IN $trashed;
+        "#;
+
+        self.db.query(sql).bind(("before", before)).await?;
+
+        Ok(())
+    }
+
     pub async fn list(
         &self,
         category_id: RecordId,
         start: Datetime,
         end: Datetime,
+        tag_id: Option<RecordId>,
     ) -> Result<Vec<Transaction>, DbError> {
+        // Split transactions are listed under every category they are split
+        // into, with `amount` narrowed down to that category's share.
         let sql = r#"
         SELECT
             id,
             amount,
             note,
-            date
+            date,
+            account,
+            payee,
+            refund_of,
+            refund_kind,
+            installment,
+            installment_number,
+            fn::transaction_status(status, date) AS status,
+            IF location THEN {
+                lat: location.coordinates[1],
+                lon: location.coordinates[0],
+                place: place
+            } END AS location,
+            ->transaction_tag.out AS tags
         FROM $category<-user_category->category_transaction.out
-        WHERE date IN $start..=$end
+        WHERE
+            date IN $start..=$end
+            AND deleted_at = NONE
+            AND array::len(->transaction_split) = 0
+            AND (!$tag OR $tag IN ->transaction_tag.out);
+        SELECT
+            in AS id,
+            amount,
+            in.note AS note,
+            in.date AS date,
+            in.account AS account,
+            in.payee AS payee,
+            in.refund_of AS refund_of,
+            in.refund_kind AS refund_kind,
+            in.installment AS installment,
+            in.installment_number AS installment_number,
+            fn::transaction_status(in.status, in.date) AS status,
+            IF in.location THEN {
+                lat: in.location.coordinates[1],
+                lon: in.location.coordinates[0],
+                place: in.place
+            } END AS location,
+            in->transaction_tag.out AS tags,
+            (
+                SELECT out AS category, amount
+                FROM transaction_split
+                WHERE in = $parent.in
+            ) AS splits
+        FROM $category<-transaction_split
+        WHERE
+            in.date IN $start..=$end
+            AND in.deleted_at = NONE
+            AND (!$tag OR $tag IN in->transaction_tag.out);
         "#;
 
-        Ok(self
+        let mut res = self
             .db
             .query(sql)
             .bind(("category", category_id))
             .bind(("start", start))
             .bind(("end", end))
+            .bind(("tag", tag_id))
+            .await?;
+
+        let mut transactions = res.take::<Vec<Transaction>>(0)?;
+        transactions.extend(res.take::<Vec<Transaction>>(1)?);
+
+        Ok(transactions)
+    }
+
+    /// Lists the user's transactions dated within `start..=end`, oldest first,
+    /// each under the category it was filed in. Refunds are left out, as they
+    /// would otherwise pass for second entries of what they pay back.
+    pub async fn summaries(
+        &self,
+        user_id: RecordId,
+        start: Datetime,
+        end: Datetime,
+    ) -> Result<Vec<TransactionSummary>, DbError> {
+        let sql = r#"
+        SELECT
+            out AS id,
+            in.out AS category,
+            out.amount AS amount,
+            out.note AS note,
+            out.date AS date
+        FROM $user->user_category->category_transaction
+        WHERE
+            out.date IN $start..=$end
+            AND out.deleted_at = NONE
+            AND out.refund_of = NONE
+        ORDER BY date;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("start", start))
+            .bind(("end", end))
+            .await?
+            .take(0)?)
+    }
+
+    /// Groups the user's transactions since `since` by category, amount and
+    /// note, returning the combinations logged at least twice, most frequent
+    /// first. Refunds and installments are left out as they are not entered
+    /// by hand.
+    pub async fn frequent(
+        &self,
+        user_id: RecordId,
+        since: Datetime,
+        limit: usize,
+    ) -> Result<Vec<FrequentEntry>, DbError> {
+        let sql = r#"
+        SELECT *
+        FROM (
+            SELECT
+                category,
+                amount,
+                note,
+                count() AS count,
+                time::max(date) AS last_date
+            FROM (
+                SELECT
+                    in.out AS category,
+                    out.amount AS amount,
+                    out.note AS note,
+                    out.date AS date
+                FROM $user->user_category->category_transaction
+                WHERE
+                    out.date >= $since
+                    AND out.deleted_at = NONE
+                    AND out.refund_of = NONE
+                    AND out.installment = NONE
+                    AND in.out.deleted_at = NONE
+            )
+            GROUP BY category, amount, note
+        )
+        WHERE count > 1
+        ORDER BY count DESC, last_date DESC
+        LIMIT $limit;
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("since", since))
+            .bind(("limit", limit))
+            .await?
+            .take(0)?)
+    }
+
+    /// Lists the user's live, unreconciled transactions, optionally bounded by
+    /// date, for rules to be applied to.
+    pub async fn rule_targets(
+        &self,
+        user_id: RecordId,
+        start: Option<Datetime>,
+        end: Option<Datetime>,
+    ) -> Result<Vec<RuleTarget>, DbError> {
+        let sql = r#"
+        SELECT
+            id,
+            array::first(<-category_transaction.in.out) AS category,
+            amount,
+            note,
+            payee,
+            ->transaction_tag.out AS tags,
+            array::len(->transaction_split) > 0 AS split
+        FROM $user->user_category->category_transaction.out
+        WHERE
+            deleted_at = NONE
+            AND status != 'reconciled'
+            AND (!$start OR date >= $start)
+            AND (!$end OR date <= $end);
+        "#;
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("start", start))
+            .bind(("end", end))
             .await?
             .take(0)?)
     }
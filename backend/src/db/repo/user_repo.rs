@@ -1,6 +1,7 @@
 use crate::db::{ApiDb, DbError};
 use email_address::EmailAddress;
 use serde_json::json;
+use surrealdb::RecordId;
 
 pub struct UserRepo<'a> {
     db: &'a ApiDb,
@@ -44,7 +45,7 @@ impl<'a> UserRepo<'a> {
         email: EmailAddress,
         username: String,
         password_hash: String,
-    ) -> Result<surrealdb::RecordId, DbError> {
+    ) -> Result<RecordId, DbError> {
         let sql = r#"
         CREATE user SET
             email = $email,
@@ -86,4 +87,31 @@ impl<'a> UserRepo<'a> {
                 json!({"username": "No user found with that username"}),
             ))
     }
+
+    pub async fn timezone(&self, user_id: RecordId) -> Result<Option<String>, DbError> {
+        let sql = "SELECT VALUE timezone FROM ONLY $user;";
+
+        Ok(self
+            .db
+            .query(sql)
+            .bind(("user", user_id))
+            .await?
+            .take::<Option<_>>(0)?)
+    }
+
+    pub async fn set_timezone(
+        &self,
+        user_id: RecordId,
+        timezone: Option<String>,
+    ) -> Result<(), DbError> {
+        let sql = "UPDATE ONLY $user SET timezone = $timezone;";
+
+        self.db
+            .query(sql)
+            .bind(("user", user_id))
+            .bind(("timezone", timezone))
+            .await?;
+
+        Ok(())
+    }
 }
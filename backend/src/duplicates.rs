@@ -0,0 +1,106 @@
+use chrono::Duration;
+use surrealdb::{Datetime, RecordId};
+
+use crate::{
+    config::config,
+    models::{DuplicatePair, TransactionSummary},
+    recurrence::to_utc,
+};
+
+fn words(note: &str) -> Vec<String> {
+    let mut words = Vec::<String>::new();
+
+    for word in note
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+    {
+        if !words.contains(&word) {
+            words.push(word);
+        }
+    }
+
+    words
+}
+
+/// Jaccard similarity of the words in two notes, between 0 and 1.
+pub fn note_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (words(a), words(b));
+
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+
+    let shared = a.iter().filter(|word| b.contains(word)).count();
+
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+/// How far apart two entries of the same purchase can be dated.
+pub fn window() -> Duration {
+    Duration::days(config().duplicates.window_days)
+}
+
+/// Whether a transaction with `amount`, `date` and `note` looks like a second
+/// entry of `other`. Notes are only compared when both entries have one, as
+/// the second person logging a purchase often doesn't bother.
+pub fn is_duplicate(
+    amount: f64,
+    date: &Datetime,
+    note: Option<&str>,
+    other: &TransactionSummary,
+) -> bool {
+    let duplicates = &config().duplicates;
+
+    let largest = amount.abs().max(other.amount.abs());
+    if (amount - other.amount).abs() > largest * duplicates.amount_tolerance {
+        return false;
+    }
+
+    if (to_utc(date) - to_utc(&other.date)).abs() > window() {
+        return false;
+    }
+
+    match (note, other.note.as_deref()) {
+        (Some(a), Some(b)) => note_similarity(a, b) >= duplicates.note_similarity,
+        _ => true,
+    }
+}
+
+/// Pairs up likely duplicates among `transactions`, which must be ordered by
+/// date, skipping the pairs in `dismissed`.
+pub fn find_pairs(
+    transactions: Vec<TransactionSummary>,
+    dismissed: &[Vec<RecordId>],
+) -> Vec<DuplicatePair> {
+    let is_dismissed = |a: &RecordId, b: &RecordId| {
+        dismissed
+            .iter()
+            .any(|pair| pair.contains(a) && pair.contains(b))
+    };
+
+    let mut pairs = Vec::new();
+
+    for (i, original) in transactions.iter().enumerate() {
+        for duplicate in &transactions[i + 1..] {
+            if to_utc(&duplicate.date) - to_utc(&original.date) > window() {
+                break;
+            }
+
+            if is_duplicate(
+                duplicate.amount,
+                &duplicate.date,
+                duplicate.note.as_deref(),
+                original,
+            ) && !is_dismissed(&original.id, &duplicate.id)
+            {
+                pairs.push(DuplicatePair {
+                    original: original.clone(),
+                    duplicate: duplicate.clone(),
+                });
+            }
+        }
+    }
+
+    pairs
+}
@@ -0,0 +1,207 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Deserialize;
+
+use crate::import::{ImportRow, ParsedImport, parse_amount};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+#[derive(Deserialize)]
+pub struct CsvMapping {
+    pub date: Column,
+    pub amount: Column,
+    pub note: Option<Column>,
+    pub category: Option<Column>,
+}
+
+/// How the file writes spending: as positive amounts, or as negative ones the
+/// way bank exports and OFX and QIF statements list debits.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExpenseSign {
+    Negative,
+    #[default]
+    Positive,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvOptions {
+    pub mapping: CsvMapping,
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    #[serde(default = "default_decimal_separator")]
+    pub decimal_separator: char,
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    #[serde(default = "default_has_headers")]
+    pub has_headers: bool,
+    /// Rows with the opposite sign are credits and are skipped.
+    #[serde(default)]
+    pub expense_sign: ExpenseSign,
+    pub default_category: Option<String>,
+    #[serde(default)]
+    pub create_categories: bool,
+}
+
+fn default_date_format() -> String {
+    "%Y-%m-%d".into()
+}
+
+fn default_decimal_separator() -> char {
+    '.'
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_has_headers() -> bool {
+    true
+}
+
+struct Columns {
+    date: usize,
+    amount: usize,
+    note: Option<usize>,
+    category: Option<usize>,
+}
+
+fn resolve_column(
+    column: &Column,
+    headers: Option<&csv::StringRecord>,
+    field: &str,
+) -> Result<usize, String> {
+    match column {
+        Column::Index(index) => Ok(*index),
+        Column::Name(name) => headers
+            .and_then(|headers| {
+                headers
+                    .iter()
+                    .position(|header| header.trim().eq_ignore_ascii_case(name.trim()))
+            })
+            .ok_or_else(|| format!("Column \"{name}\" for {field} was not found in the header")),
+    }
+}
+
+fn parse_date(raw: &str, format: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let raw = raw.trim();
+
+    NaiveDateTime::parse_from_str(raw, format)
+        .or_else(|_| {
+            NaiveDate::parse_from_str(raw, format).map(|date| date.and_time(Default::default()))
+        })
+        .ok()
+        .map(|date| date.and_utc())
+}
+
+fn optional_field(record: &csv::StringRecord, index: Option<usize>) -> Option<String> {
+    index
+        .and_then(|index| record.get(index))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+}
+
+/// Parses `bytes` as CSV according to `options`. Problems with individual rows
+/// are collected in the result; only an unusable file or mapping is an `Err`.
+pub fn parse(bytes: &[u8], options: &CsvOptions) -> Result<ParsedImport, String> {
+    if !options.delimiter.is_ascii() {
+        return Err("Delimiter must be a single ASCII character".into());
+    }
+
+    if options.decimal_separator != '.' && options.decimal_separator != ',' {
+        return Err("Decimal separator must be \".\" or \",\"".into());
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter as u8)
+        .has_headers(options.has_headers)
+        .flexible(true)
+        .from_reader(bytes);
+
+    let headers = if options.has_headers {
+        Some(reader.headers().map_err(|e| e.to_string())?.clone())
+    } else {
+        None
+    };
+
+    let mapping = &options.mapping;
+    let columns = Columns {
+        date: resolve_column(&mapping.date, headers.as_ref(), "date")?,
+        amount: resolve_column(&mapping.amount, headers.as_ref(), "amount")?,
+        note: mapping
+            .note
+            .as_ref()
+            .map(|column| resolve_column(column, headers.as_ref(), "note"))
+            .transpose()?,
+        category: mapping
+            .category
+            .as_ref()
+            .map(|column| resolve_column(column, headers.as_ref(), "category"))
+            .transpose()?,
+    };
+
+    let mut parsed = ParsedImport::default();
+
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line() as usize);
+                parsed.error(line, e.to_string());
+                continue;
+            }
+        };
+
+        let line = record.position().map_or(0, |p| p.line() as usize);
+
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+
+        let Some(date) = record
+            .get(columns.date)
+            .and_then(|raw| parse_date(raw, &options.date_format))
+        else {
+            parsed.error(
+                line,
+                format!("Invalid date, expected format {}", options.date_format),
+            );
+            continue;
+        };
+
+        let Some(amount) = record
+            .get(columns.amount)
+            .and_then(|raw| parse_amount(raw, options.decimal_separator))
+        else {
+            parsed.error(line, "Invalid amount");
+            continue;
+        };
+
+        let amount = match options.expense_sign {
+            ExpenseSign::Negative => -amount,
+            ExpenseSign::Positive => amount,
+        };
+
+        if amount <= 0.0 {
+            parsed.skip(line);
+            continue;
+        }
+
+        parsed.rows.push(ImportRow {
+            line,
+            date: date.into(),
+            amount,
+            note: optional_field(&record, columns.note),
+            category: optional_field(&record, columns.category),
+            fitid: None,
+        });
+    }
+
+    Ok(parsed)
+}
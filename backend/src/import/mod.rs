@@ -0,0 +1,66 @@
+pub mod csv;
+pub mod ofx;
+pub mod qif;
+
+use serde::Serialize;
+use surrealdb::Datetime;
+
+/// A statement entry parsed from an uploaded file, before its category has
+/// been resolved against the user's categories.
+#[derive(Clone, Serialize)]
+pub struct ImportRow {
+    pub line: usize,
+    pub date: Datetime,
+    pub amount: f64,
+    pub note: Option<String>,
+    pub category: Option<String>,
+    /// The bank's transaction id, used to skip entries on re-import.
+    pub fitid: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ImportError {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Default)]
+pub struct ParsedImport {
+    pub rows: Vec<ImportRow>,
+    pub errors: Vec<ImportError>,
+    /// Line numbers of credit entries, which aren't expenses and are left out.
+    pub skipped: Vec<usize>,
+}
+
+impl ParsedImport {
+    pub fn error(&mut self, line: usize, message: impl Into<String>) {
+        self.errors.push(ImportError {
+            line,
+            message: message.into(),
+        });
+    }
+
+    pub fn skip(&mut self, line: usize) {
+        self.skipped.push(line);
+    }
+}
+
+/// Parses a bank-formatted amount such as `"1.234,56"` or `"$ -12.50"`. Any
+/// character other than digits, the sign and `decimal_separator` is treated as
+/// a thousands separator or currency symbol and dropped.
+pub fn parse_amount(raw: &str, decimal_separator: char) -> Option<f64> {
+    let normalized = raw
+        .trim()
+        .chars()
+        .filter_map(|c| match c {
+            '0'..='9' | '-' => Some(c),
+            c if c == decimal_separator => Some('.'),
+            _ => None,
+        })
+        .collect::<String>();
+
+    normalized
+        .parse::<f64>()
+        .ok()
+        .filter(|amount| amount.is_finite())
+}
@@ -0,0 +1,154 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use serde::Deserialize;
+
+use crate::import::{ImportRow, ParsedImport, parse_amount};
+
+/// Statements carry no categories, so every entry is filed under
+/// `default_category`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfxOptions {
+    pub default_category: Option<String>,
+}
+
+#[derive(Default)]
+struct Entry {
+    line: usize,
+    posted: Option<String>,
+    amount: Option<String>,
+    fitid: Option<String>,
+    name: Option<String>,
+    memo: Option<String>,
+}
+
+/// Parses an OFX timestamp such as `20260102`, `20260102123000.000` or
+/// `20260102123000[-5:EST]`.
+fn parse_date(raw: &str) -> Option<DateTime<Utc>> {
+    let (stamp, offset) = match raw.split_once('[') {
+        Some((stamp, zone)) => (stamp, zone.trim_end_matches(']').split(':').next()),
+        None => (raw, None),
+    };
+
+    let digits = stamp.split('.').next()?;
+    let date = NaiveDate::parse_from_str(digits.get(..8)?, "%Y%m%d").ok()?;
+    let time = match digits.get(8..14) {
+        Some(time) => NaiveTime::parse_from_str(time, "%H%M%S").ok()?,
+        None => NaiveTime::default(),
+    };
+
+    let offset = match offset {
+        Some(hours) => {
+            let seconds = (hours.trim().parse::<f64>().ok()? * 3600.0) as i32;
+            FixedOffset::east_opt(seconds)?
+        }
+        None => FixedOffset::east_opt(0)?,
+    };
+
+    offset
+        .from_local_datetime(&NaiveDateTime::new(date, time))
+        .single()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+fn note(name: Option<String>, memo: Option<String>) -> Option<String> {
+    match (name, memo) {
+        (Some(name), Some(memo)) if !memo.eq_ignore_ascii_case(&name) => {
+            Some(format!("{name} ({memo})"))
+        }
+        (Some(name), _) => Some(name),
+        (None, memo) => memo,
+    }
+}
+
+fn push_entry(parsed: &mut ParsedImport, entry: Entry) {
+    let Some(date) = entry.posted.as_deref().and_then(parse_date) else {
+        parsed.error(entry.line, "Invalid or missing DTPOSTED");
+        return;
+    };
+
+    let Some(amount) = entry
+        .amount
+        .as_deref()
+        .and_then(|raw| parse_amount(raw, '.'))
+    else {
+        parsed.error(entry.line, "Invalid or missing TRNAMT");
+        return;
+    };
+
+    // Statements report spending as negative amounts; deposits aren't expenses.
+    if amount >= 0.0 {
+        parsed.skip(entry.line);
+        return;
+    }
+
+    parsed.rows.push(ImportRow {
+        line: entry.line,
+        date: date.into(),
+        amount: -amount,
+        note: note(entry.name, entry.memo),
+        category: None,
+        fitid: entry.fitid,
+    });
+}
+
+/// Parses the `<STMTTRN>` entries of an OFX or QFX statement. Both the SGML
+/// (OFX 1.x, unclosed leaf tags) and XML (OFX 2.x) flavours are accepted.
+pub fn parse(bytes: &[u8]) -> Result<ParsedImport, String> {
+    let text = String::from_utf8_lossy(bytes);
+
+    if !text.contains("<OFX>") && !text.contains("<ofx>") {
+        return Err("File is not an OFX or QFX statement".into());
+    }
+
+    let mut parsed = ParsedImport::default();
+    let mut entry = None::<Entry>;
+
+    // Byte offsets of every tag, so line numbers count the SGML header too.
+    let starts = text.match_indices('<').map(|(i, _)| i).collect::<Vec<_>>();
+    let mut line = 1;
+    let mut counted = 0;
+
+    for (n, &start) in starts.iter().enumerate() {
+        let end = starts.get(n + 1).copied().unwrap_or(text.len());
+        let chunk = &text[start + 1..end];
+
+        let Some((tag, value)) = chunk.split_once('>') else {
+            continue;
+        };
+
+        let tag = tag.trim().to_ascii_uppercase();
+        let value = value.trim();
+        let value = (!value.is_empty()).then(|| value.to_string());
+
+        match tag.as_str() {
+            "STMTTRN" => {
+                line += text[counted..start].matches('\n').count();
+                counted = start;
+
+                entry = Some(Entry {
+                    line,
+                    ..Default::default()
+                })
+            }
+            "/STMTTRN" => {
+                if let Some(entry) = entry.take() {
+                    push_entry(&mut parsed, entry);
+                }
+            }
+            _ => {
+                if let Some(ref mut entry) = entry {
+                    match tag.as_str() {
+                        "DTPOSTED" => entry.posted = value,
+                        "TRNAMT" => entry.amount = value,
+                        "FITID" => entry.fitid = value,
+                        "NAME" | "PAYEE" => entry.name = entry.name.take().or(value),
+                        "MEMO" => entry.memo = value,
+                        _ => (),
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(parsed)
+}
@@ -0,0 +1,170 @@
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+
+use crate::{
+    category_tree::PATH_SEPARATOR,
+    import::{ImportRow, ParsedImport, parse_amount},
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QifOptions {
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    #[serde(default = "default_decimal_separator")]
+    pub decimal_separator: char,
+    pub default_category: Option<String>,
+    #[serde(default)]
+    pub create_categories: bool,
+}
+
+fn default_date_format() -> String {
+    "%m/%d/%Y".into()
+}
+
+fn default_decimal_separator() -> char {
+    '.'
+}
+
+#[derive(Default)]
+struct Entry {
+    line: usize,
+    date: Option<String>,
+    amount: Option<String>,
+    payee: Option<String>,
+    memo: Option<String>,
+    category: Option<String>,
+}
+
+/// Parses a QIF date, accepting Quicken's `'` year separator and two-digit
+/// years in place of the four-digit `%Y` of `format`.
+fn parse_date(raw: &str, format: &str) -> Option<chrono::DateTime<Utc>> {
+    let raw = raw.trim().replace('\'', "/").replace(' ', "");
+
+    // `%Y` happily reads `26` as the year 26, so the short form goes first.
+    NaiveDate::parse_from_str(&raw, &format.replace("%Y", "%y"))
+        .or_else(|_| NaiveDate::parse_from_str(&raw, format))
+        .ok()
+        .map(|date| date.and_time(Default::default()).and_utc())
+}
+
+fn push_entry(parsed: &mut ParsedImport, entry: Entry, options: &QifOptions) {
+    let Some(date) = entry
+        .date
+        .as_deref()
+        .and_then(|raw| parse_date(raw, &options.date_format))
+    else {
+        parsed.error(
+            entry.line,
+            format!("Invalid date, expected format {}", options.date_format),
+        );
+        return;
+    };
+
+    let Some(amount) = entry
+        .amount
+        .as_deref()
+        .and_then(|raw| parse_amount(raw, options.decimal_separator))
+    else {
+        parsed.error(entry.line, "Invalid or missing amount");
+        return;
+    };
+
+    if amount >= 0.0 {
+        parsed.skip(entry.line);
+        return;
+    }
+
+    let note = match (entry.payee, entry.memo) {
+        (Some(payee), Some(memo)) => Some(format!("{payee} ({memo})")),
+        (payee, memo) => payee.or(memo),
+    };
+
+    // Subcategories are written as `Parent:Child`, and transfers as `[Account]`.
+    let category = entry
+        .category
+        .filter(|category| !category.starts_with('['))
+        .map(|category| {
+            category
+                .split(':')
+                .map(str::trim)
+                .filter(|level| !level.is_empty())
+                .collect::<Vec<_>>()
+                .join(&format!(" {PATH_SEPARATOR} "))
+        })
+        .filter(|category| !category.is_empty());
+
+    parsed.rows.push(ImportRow {
+        line: entry.line,
+        date: date.into(),
+        amount: -amount,
+        note,
+        category,
+        fitid: None,
+    });
+}
+
+/// Parses the bank or cash transactions of a QIF file. Investment and memorized
+/// transaction sections are ignored.
+pub fn parse(bytes: &[u8], options: &QifOptions) -> Result<ParsedImport, String> {
+    if options.decimal_separator != '.' && options.decimal_separator != ',' {
+        return Err("Decimal separator must be \".\" or \",\"".into());
+    }
+
+    let text = String::from_utf8_lossy(bytes);
+
+    let mut parsed = ParsedImport::default();
+    let mut entry = None::<Entry>;
+    let mut section = String::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim_end();
+
+        if let Some(header) = line.strip_prefix('!') {
+            section = header.to_ascii_lowercase();
+            entry = None;
+            continue;
+        }
+
+        if !matches!(
+            section.as_str(),
+            "type:bank" | "type:cash" | "type:ccard" | "type:oth l" | "type:oth a"
+        ) {
+            continue;
+        }
+
+        let Some(code) = line.chars().next() else {
+            continue;
+        };
+        let value = line[code.len_utf8()..].trim();
+
+        if code == '^' {
+            if let Some(entry) = entry.take() {
+                push_entry(&mut parsed, entry, options);
+            }
+            continue;
+        }
+
+        let entry = entry.get_or_insert_with(|| Entry {
+            line: line_number,
+            ..Default::default()
+        });
+        let value = (!value.is_empty()).then(|| value.to_string());
+
+        match code {
+            'D' => entry.date = value,
+            'T' | 'U' => entry.amount = entry.amount.take().or(value),
+            'P' => entry.payee = value,
+            'M' => entry.memo = value,
+            'L' => entry.category = value,
+            _ => (),
+        }
+    }
+
+    if parsed.rows.is_empty() && parsed.errors.is_empty() && section.is_empty() {
+        return Err("File is not a QIF statement".into());
+    }
+
+    Ok(parsed)
+}
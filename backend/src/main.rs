@@ -4,9 +4,19 @@
 mod macros;
 
 mod api;
+mod billing;
+mod category_tree;
+mod clusters;
 mod config;
 mod db;
+mod duplicates;
+mod import;
 mod models;
+mod quick_add;
+mod recurrence;
+mod rules;
+mod scheduler;
+mod storage;
 mod util;
 
 use std::{net::SocketAddr, path::PathBuf};
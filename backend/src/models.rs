@@ -1,3 +1,4 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize, Serializer};
 use surrealdb::{Datetime, RecordId};
 
@@ -8,6 +9,23 @@ where
     s.serialize_str(&id.key().to_string())
 }
 
+pub fn serialize_record_ids<S>(ids: &[RecordId], s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    s.collect_seq(ids.iter().map(|id| id.key().to_string()))
+}
+
+pub fn serialize_option_record_id<S>(id: &Option<RecordId>, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match id {
+        Some(id) => serialize_record_id(id, s),
+        None => s.serialize_none(),
+    }
+}
+
 #[derive(Deserialize)]
 pub struct UserAuth {
     pub id: RecordId,
@@ -16,14 +34,44 @@ pub struct UserAuth {
     pub password_hash: String,
 }
 
+/// `amount` and `transactions` cover the category's own transactions, while
+/// the `total_` fields also include those of its subcategories.
 #[derive(Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
 pub struct Category {
     #[serde(serialize_with = "serialize_record_id")]
     pub id: RecordId,
+    #[serde(default, serialize_with = "serialize_option_record_id")]
+    pub parent: Option<RecordId>,
     pub name: String,
     pub icon: String,
     pub amount: f64,
     pub transactions: usize,
+    #[serde(default)]
+    pub total_amount: f64,
+    #[serde(default)]
+    pub total_transactions: usize,
+    #[serde(default)]
+    pub children: Vec<Category>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct TrashedCategory {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    pub name: String,
+    pub icon: String,
+    pub deleted_at: Datetime,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct CategoryName {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    pub name: String,
+    #[serde(serialize_with = "serialize_option_record_id")]
+    pub parent: Option<RecordId>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -33,6 +81,230 @@ pub struct Transaction {
     pub amount: f64,
     pub note: Option<String>,
     pub date: Datetime,
+    #[serde(serialize_with = "serialize_option_record_id")]
+    pub account: Option<RecordId>,
+    #[serde(default)]
+    pub splits: Vec<Split>,
+    #[serde(default, serialize_with = "serialize_record_ids")]
+    pub tags: Vec<RecordId>,
+    #[serde(default, serialize_with = "serialize_option_record_id")]
+    pub payee: Option<RecordId>,
+    /// Only set when the server picked the category, such as when a rule
+    /// filed the transaction somewhere other than the requested category.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_record_id"
+    )]
+    pub category: Option<RecordId>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_record_id"
+    )]
+    pub refund_of: Option<RecordId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refund_kind: Option<RefundKind>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_record_id"
+    )]
+    pub installment: Option<RecordId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installment_number: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<TransactionStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+}
+
+/// Where a transaction stands with the bank. Unless set explicitly it follows
+/// the date, through `fn::transaction_status`: pending until the transaction
+/// falls due, cleared from then on.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionStatus {
+    Pending,
+    Cleared,
+    Reconciled,
+}
+
+/// A bill falling due soon, either a pending transaction or an upcoming
+/// occurrence of a recurring transaction that has not been created yet.
+#[derive(Deserialize, Serialize)]
+pub struct UpcomingBill {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub category: RecordId,
+    pub amount: f64,
+    pub note: Option<String>,
+    pub date: Datetime,
+    #[serde(default, serialize_with = "serialize_option_record_id")]
+    pub transaction: Option<RecordId>,
+    #[serde(default, serialize_with = "serialize_option_record_id")]
+    pub recurring: Option<RecordId>,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RefundKind {
+    Refund,
+    Reimbursement,
+}
+
+/// What a refund of a transaction inherits from it, along with how much of
+/// it has already been paid back.
+#[derive(Deserialize)]
+pub struct Refundable {
+    pub amount: f64,
+    pub category: RecordId,
+    pub account: Option<RecordId>,
+    pub payee: Option<RecordId>,
+    pub splits: Vec<Split>,
+    pub tags: Vec<RecordId>,
+    pub refund_of: Option<RecordId>,
+    pub refunded: f64,
+}
+
+#[derive(Serialize)]
+pub struct Refunds {
+    pub amount: f64,
+    pub refunded: f64,
+    pub remaining: f64,
+    pub refunds: Vec<Transaction>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PendingReimbursement {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    #[serde(serialize_with = "serialize_record_id")]
+    pub category: RecordId,
+    pub amount: f64,
+    pub note: Option<String>,
+    pub date: Datetime,
+    pub repaid: f64,
+    pub outstanding: f64,
+}
+
+/// The user-supplied fields of a transaction, as written by
+/// `TransactionRepo::create` and `TransactionRepo::edit`.
+#[derive(Clone)]
+pub struct TransactionDraft {
+    pub account: Option<RecordId>,
+    pub amount: f64,
+    pub note: Option<String>,
+    pub date: Datetime,
+    pub splits: Vec<Split>,
+    pub tags: Vec<RecordId>,
+    pub payee: Option<RecordId>,
+}
+
+/// The user-supplied fields of a transaction as recorded in a revision.
+#[derive(Deserialize, Serialize)]
+pub struct TransactionSnapshot {
+    #[serde(serialize_with = "serialize_option_record_id")]
+    pub account: Option<RecordId>,
+    #[serde(serialize_with = "serialize_option_record_id")]
+    pub payee: Option<RecordId>,
+    pub amount: f64,
+    pub note: Option<String>,
+    pub date: Datetime,
+    pub splits: Vec<Split>,
+    #[serde(serialize_with = "serialize_record_ids")]
+    pub tags: Vec<RecordId>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct Revision {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    #[serde(serialize_with = "serialize_record_id")]
+    pub user: RecordId,
+    pub username: String,
+    pub before: TransactionSnapshot,
+    pub after: TransactionSnapshot,
+    pub created_at: Datetime,
+}
+
+/// A comment on a transaction. Replies point at the comment they answer
+/// through `parent`.
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct Comment {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    #[serde(serialize_with = "serialize_option_record_id")]
+    pub parent: Option<RecordId>,
+    #[serde(serialize_with = "serialize_record_id")]
+    pub author: RecordId,
+    pub username: String,
+    pub body: String,
+    pub created_at: Datetime,
+    pub updated_at: Datetime,
+}
+
+/// The fields of a transaction compared by duplicate detection.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct TransactionSummary {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    #[serde(serialize_with = "serialize_record_id")]
+    pub category: RecordId,
+    pub amount: f64,
+    pub note: Option<String>,
+    pub date: Datetime,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct TrashedTransaction {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    #[serde(serialize_with = "serialize_record_id")]
+    pub category: RecordId,
+    pub amount: f64,
+    pub note: Option<String>,
+    pub date: Datetime,
+    pub deleted_at: Datetime,
+}
+
+#[derive(Serialize)]
+pub struct Trash {
+    pub categories: Vec<TrashedCategory>,
+    pub transactions: Vec<TrashedTransaction>,
+}
+
+#[derive(Serialize)]
+pub struct DuplicatePair {
+    pub original: TransactionSummary,
+    pub duplicate: TransactionSummary,
+}
+
+/// A change applied to a single transaction by a bulk operation.
+pub enum BulkAction {
+    Delete,
+    MoveCategory(RecordId),
+    SetNote(Option<String>),
+    AddTag(RecordId),
+    ShiftDate(i64),
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Split {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub category: RecordId,
+    pub amount: f64,
+}
+
+/// Where a transaction happened. The coordinates are stored as a geometry
+/// point and the place name alongside it.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Location {
+    pub lat: f64,
+    pub lon: f64,
+    pub place: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -46,4 +318,356 @@ pub struct Expense {
 pub struct ExpensesOverview {
     pub daily_expense: Vec<Expense>,
     pub categories: Vec<Category>,
+    pub tags: Vec<TagTotal>,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountKind {
+    Cash,
+    Debit,
+    Credit,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct Account {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    pub name: String,
+    pub kind: AccountKind,
+    pub opening_balance: f64,
+    pub statement_day: Option<u32>,
+    pub balance: f64,
+}
+
+/// Spending on a credit account between two statements, both dates
+/// inclusive.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BillingCycle {
+    pub opens: NaiveDate,
+    pub closes: NaiveDate,
+    pub amount: f64,
+    pub transactions: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct Reconciliation {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    #[serde(serialize_with = "serialize_record_id")]
+    pub account: RecordId,
+    pub statement_date: Datetime,
+    pub closing_balance: f64,
+    /// The account balance as of the statement date, counting only cleared
+    /// and reconciled transactions.
+    pub cleared_balance: f64,
+    pub difference: f64,
+    pub finished_at: Option<Datetime>,
+}
+
+/// A transaction on the account being reconciled that is not locked yet.
+#[derive(Deserialize, Serialize)]
+pub struct ReconcileItem {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    pub amount: f64,
+    pub note: Option<String>,
+    pub date: Datetime,
+    pub status: TransactionStatus,
+}
+
+#[derive(Serialize)]
+pub struct ReconciliationReport {
+    #[serde(flatten)]
+    pub reconciliation: Reconciliation,
+    pub transactions: Vec<ReconcileItem>,
+}
+
+#[derive(Deserialize)]
+pub struct AccountEntry {
+    pub date: Datetime,
+    pub amount: f64,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct Transfer {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    #[serde(serialize_with = "serialize_record_id")]
+    pub from_account: RecordId,
+    #[serde(serialize_with = "serialize_record_id")]
+    pub to_account: RecordId,
+    pub amount: f64,
+    pub note: Option<String>,
+    pub date: Datetime,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RecurringSchedule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub start: Datetime,
+    pub until: Option<Datetime>,
+    pub count: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct Recurring {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    #[serde(serialize_with = "serialize_record_id")]
+    pub category: RecordId,
+    #[serde(serialize_with = "serialize_option_record_id")]
+    pub account: Option<RecordId>,
+    pub amount: f64,
+    pub note: Option<String>,
+    pub schedule: RecurringSchedule,
+    pub occurrences: u32,
+    pub next_date: Option<Datetime>,
+    pub stopped: bool,
+}
+
+/// A purchase paid off in monthly installments, each of which is a
+/// transaction of its own.
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct InstallmentPlan {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    #[serde(serialize_with = "serialize_record_id")]
+    pub category: RecordId,
+    #[serde(serialize_with = "serialize_option_record_id")]
+    pub account: Option<RecordId>,
+    pub note: Option<String>,
+    pub total: f64,
+    pub count: u32,
+    pub start: Datetime,
+    pub paid: f64,
+    pub paid_count: u32,
+    pub remaining: f64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Occurrence {
+    pub date: Datetime,
+    pub skipped: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct Attachment {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    pub name: String,
+    pub mime: String,
+    pub size: u64,
+    #[serde(skip_serializing)]
+    pub hash: String,
+    pub created_at: Datetime,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Tag {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct TagTotal {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    pub name: String,
+    pub amount: f64,
+    pub transactions: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Payee {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    pub name: String,
+    pub aliases: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PayeeTotal {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    pub name: String,
+    pub amount: f64,
+    pub transactions: usize,
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct RuleConditions {
+    pub note_contains: Option<String>,
+    pub note_regex: Option<String>,
+    pub min_amount: Option<f64>,
+    pub max_amount: Option<f64>,
+    #[serde(serialize_with = "serialize_option_record_id")]
+    pub payee: Option<RecordId>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Rule {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    pub name: String,
+    pub priority: i64,
+    pub conditions: RuleConditions,
+    #[serde(serialize_with = "serialize_option_record_id")]
+    pub category: Option<RecordId>,
+    #[serde(serialize_with = "serialize_record_ids")]
+    pub tags: Vec<RecordId>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Template {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    pub name: String,
+    #[serde(serialize_with = "serialize_record_id")]
+    pub category: RecordId,
+    pub amount: f64,
+    pub note: Option<String>,
+    #[serde(serialize_with = "serialize_record_ids")]
+    pub tags: Vec<RecordId>,
+    pub uses: u32,
+}
+
+/// An entry the user keeps logging, as a candidate for a template.
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct FrequentEntry {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub category: RecordId,
+    pub amount: f64,
+    pub note: Option<String>,
+    pub count: u32,
+    pub last_date: Datetime,
+}
+
+/// An existing transaction as seen by `POST /rules/apply`.
+#[derive(Deserialize)]
+pub struct RuleTarget {
+    pub id: RecordId,
+    pub category: RecordId,
+    pub amount: f64,
+    pub note: Option<String>,
+    pub payee: Option<RecordId>,
+    pub tags: Vec<RecordId>,
+    pub split: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct TransactionHit {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    #[serde(serialize_with = "serialize_record_id")]
+    pub category: RecordId,
+    pub amount: f64,
+    pub note: Option<String>,
+    pub date: Datetime,
+    pub highlight: Option<String>,
+    pub score: f64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct NameHit {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    pub name: String,
+    pub highlight: Option<String>,
+    pub score: f64,
+}
+
+/// Ranked full-text matches, each list ordered by relevance.
+#[derive(Deserialize, Serialize)]
+pub struct SearchResults {
+    pub transactions: Vec<TransactionHit>,
+    pub payees: Vec<NameHit>,
+    pub categories: Vec<NameHit>,
+}
+
+/// Map bounds in degrees. `west` is greater than `east` when the box
+/// crosses the antimeridian.
+#[derive(Clone, Copy, Serialize)]
+pub struct BoundingBox {
+    pub west: f64,
+    pub south: f64,
+    pub east: f64,
+    pub north: f64,
+}
+
+/// A located transaction as shown on the spending map.
+#[derive(Deserialize, Serialize)]
+pub struct GeoTransaction {
+    #[serde(serialize_with = "serialize_record_id")]
+    pub id: RecordId,
+    #[serde(serialize_with = "serialize_record_id")]
+    pub category: RecordId,
+    pub amount: f64,
+    pub note: Option<String>,
+    pub date: Datetime,
+    pub place: Option<String>,
+    #[serde(skip_serializing)]
+    pub lat: f64,
+    #[serde(skip_serializing)]
+    pub lon: f64,
+}
+
+/// Transactions close enough together at the requested zoom to be drawn as
+/// one marker.
+#[derive(Serialize)]
+pub struct GeoCluster {
+    pub cluster: bool,
+    pub count: usize,
+    pub amount: f64,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum GeoProperties {
+    Transaction(GeoTransaction),
+    Cluster(GeoCluster),
+}
+
+#[derive(Serialize)]
+pub struct GeoPoint {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    /// `[lon, lat]`, as GeoJSON orders them.
+    pub coordinates: [f64; 2],
+}
+
+#[derive(Serialize)]
+pub struct GeoFeature {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub geometry: GeoPoint,
+    pub properties: GeoProperties,
+}
+
+/// A GeoJSON `FeatureCollection`.
+#[derive(Serialize)]
+pub struct GeoFeatureCollection {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub features: Vec<GeoFeature>,
 }
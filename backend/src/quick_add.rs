@@ -0,0 +1,252 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+
+use crate::{import::parse_amount, models::CategoryName};
+
+/// The pieces of a quick-add entry such as `12.50 coffee yesterday #work`.
+pub struct QuickEntry {
+    pub amount: f64,
+    pub date: DateTime<Utc>,
+    pub note: Option<String>,
+    pub tags: Vec<String>,
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Reads a date word relative to `now`, in `now`'s timezone. Weekday names
+/// mean the most recent such day, a week back if it is today's.
+fn parse_date(word: &str, now: DateTime<Tz>) -> Option<DateTime<Tz>> {
+    match word {
+        "today" => Some(now),
+        "yesterday" => Some(now - Duration::days(1)),
+        _ => {
+            if let Some(day) = weekday(word) {
+                let back =
+                    (now.weekday().num_days_from_monday() + 7 - day.num_days_from_monday()) % 7;
+                let back = if back == 0 { 7 } else { back };
+
+                return Some(now - Duration::days(back.into()));
+            }
+
+            NaiveDate::parse_from_str(word, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| {
+                    now.timezone()
+                        .from_local_datetime(&date.and_time(now.time()))
+                        .earliest()
+                })
+        }
+    }
+}
+
+/// Whichever of `.` and `,` comes last is the decimal separator, except that
+/// commas only grouping thousands, as in `1,200`, leave it at `.`.
+fn decimal_separator(word: &str) -> char {
+    match (word.rfind('.'), word.rfind(',')) {
+        (Some(dot), Some(comma)) if comma > dot => ',',
+        (None, Some(_)) if word.split(',').skip(1).all(|group| group.len() == 3) => '.',
+        (None, Some(_)) => ',',
+        _ => '.',
+    }
+}
+
+fn parse_money(word: &str) -> Option<f64> {
+    let word = word
+        .trim_start_matches(['$', '€', '£'])
+        .trim_end_matches(['€', '£']);
+
+    if !word.starts_with(|c: char| c.is_ascii_digit())
+        || !word
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c == ',')
+    {
+        return None;
+    }
+
+    parse_amount(word, decimal_separator(word)).filter(|amount| *amount > 0.0)
+}
+
+/// Splits `text` into an amount (the first number), a date word (`today`,
+/// `yesterday`, a weekday or `YYYY-MM-DD`, defaulting to `now`), `#tags` and
+/// whatever is left over as the note. Date words follow `now`'s timezone.
+pub fn parse(text: &str, now: DateTime<Tz>) -> Result<QuickEntry, String> {
+    let mut amount = None;
+    let mut date = None;
+    let mut tags = Vec::<String>::new();
+    let mut note = Vec::new();
+
+    for word in text.split_whitespace() {
+        if let Some(tag) = word.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.to_string());
+            }
+            continue;
+        }
+
+        if amount.is_none()
+            && let Some(money) = parse_money(word)
+        {
+            amount = Some(money);
+            continue;
+        }
+
+        if date.is_none()
+            && let Some(parsed) = parse_date(&word.to_lowercase(), now)
+        {
+            date = Some(parsed);
+            continue;
+        }
+
+        note.push(word);
+    }
+
+    let amount = amount.ok_or("No amount found")?;
+
+    Ok(QuickEntry {
+        amount,
+        date: date.unwrap_or(now).to_utc(),
+        note: (!note.is_empty()).then(|| note.join(" ")),
+        tags,
+    })
+}
+
+/// Picks the category whose name appears in the note as whole words, compared
+/// case-insensitively; longer names win so `Coffee Beans` beats `Coffee`.
+/// Between categories of the same name a top-level one wins, and when only
+/// subcategories share it nothing is guessed.
+pub fn guess_category<'a>(note: &str, categories: &'a [CategoryName]) -> Option<&'a CategoryName> {
+    let words = note
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+
+    let matches = categories
+        .iter()
+        .filter(|category| {
+            let name = category
+                .name
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .map(str::to_lowercase)
+                .collect::<Vec<_>>();
+
+            !name.is_empty() && words.windows(name.len()).any(|window| window == name)
+        })
+        .collect::<Vec<_>>();
+
+    let longest = matches
+        .iter()
+        .max_by_key(|category| category.name.len())?
+        .name
+        .to_lowercase();
+    let matches = matches
+        .into_iter()
+        .filter(|category| category.name.to_lowercase() == longest)
+        .collect::<Vec<_>>();
+
+    match matches.iter().find(|category| category.parent.is_none()) {
+        Some(category) => Some(category),
+        None if matches.len() > 1 => None,
+        None => matches.first().copied(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+    use chrono_tz::Tz;
+
+    use super::{parse, parse_money};
+
+    fn now() -> DateTime<Tz> {
+        // A Wednesday evening in Singapore, 12:30 UTC.
+        Tz::Asia__Singapore
+            .with_ymd_and_hms(2024, 5, 15, 20, 30, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_money_reads_decimal_separators() {
+        assert_eq!(parse_money("12.50"), Some(12.5));
+        assert_eq!(parse_money("12,50"), Some(12.5));
+        assert_eq!(parse_money("1.234,56"), Some(1234.56));
+        assert_eq!(parse_money("1,234.56"), Some(1234.56));
+    }
+
+    #[test]
+    fn parse_money_reads_thousands_separators() {
+        assert_eq!(parse_money("1,200"), Some(1200.0));
+        assert_eq!(parse_money("1,200,000"), Some(1_200_000.0));
+    }
+
+    #[test]
+    fn parse_money_strips_currency_symbols() {
+        assert_eq!(parse_money("$5"), Some(5.0));
+        assert_eq!(parse_money("4,20€"), Some(4.2));
+    }
+
+    #[test]
+    fn parse_money_rejects_non_amounts() {
+        assert_eq!(parse_money("coffee"), None);
+        assert_eq!(parse_money("0"), None);
+        assert_eq!(parse_money("12b"), None);
+        assert_eq!(parse_money("-5"), None);
+    }
+
+    #[test]
+    fn parse_splits_amount_date_note_and_tags() {
+        let entry = parse("12.50 coffee yesterday #work #Work", now()).unwrap();
+
+        assert_eq!(entry.amount, 12.5);
+        assert_eq!(entry.note.as_deref(), Some("coffee"));
+        assert_eq!(entry.tags, vec!["work"]);
+        assert_eq!(
+            entry.date,
+            Utc.with_ymd_and_hms(2024, 5, 14, 12, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_reads_thousands_in_amounts() {
+        let entry = parse("1,200 rent", now()).unwrap();
+
+        assert_eq!(entry.amount, 1200.0);
+        assert_eq!(entry.note.as_deref(), Some("rent"));
+        assert_eq!(entry.date, now().to_utc());
+    }
+
+    #[test]
+    fn parse_reads_weekdays_as_the_last_one() {
+        let entry = parse("mon 8 lunch", now()).unwrap();
+
+        assert_eq!(entry.amount, 8.0);
+        assert_eq!(
+            entry.date,
+            Utc.with_ymd_and_hms(2024, 5, 13, 12, 30, 0).unwrap()
+        );
+
+        let entry = parse("8 wednesday", now()).unwrap();
+
+        assert_eq!(
+            entry.date,
+            Utc.with_ymd_and_hms(2024, 5, 8, 12, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_requires_an_amount() {
+        assert!(parse("coffee today", now()).is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use chrono::{DateTime, Days, Months, Utc};
+use surrealdb::Datetime;
+
+use crate::models::{Frequency, RecurringSchedule};
+
+pub fn to_utc(date: &Datetime) -> DateTime<Utc> {
+    date.clone().into_inner().into()
+}
+
+/// Returns the `n`th (zero-based) occurrence of `schedule`, or `None` once the
+/// schedule has run past its `count` or `until` bound.
+///
+/// Occurrences are always offset from `start` rather than from the previous
+/// occurrence, so a monthly schedule starting on the 31st clamps to the end of
+/// shorter months without drifting for the rest of the year.
+pub fn occurrence(schedule: &RecurringSchedule, n: u32) -> Option<DateTime<Utc>> {
+    if schedule.count.is_some_and(|count| n >= count) {
+        return None;
+    }
+
+    let start = to_utc(&schedule.start);
+    let steps = schedule.interval.checked_mul(n)?;
+
+    let date = match schedule.frequency {
+        Frequency::Daily => start.checked_add_days(Days::new(steps.into())),
+        Frequency::Weekly => start.checked_add_days(Days::new(u64::from(steps) * 7)),
+        Frequency::Monthly => start.checked_add_months(Months::new(steps)),
+        Frequency::Yearly => start.checked_add_months(Months::new(steps.checked_mul(12)?)),
+    }?;
+
+    match schedule.until {
+        Some(ref until) if date > to_utc(until) => None,
+        _ => Some(date),
+    }
+}
@@ -0,0 +1,103 @@
+use regex::{Regex, RegexBuilder};
+use surrealdb::RecordId;
+
+use crate::models::Rule;
+
+/// The fields of a transaction that rule conditions look at.
+pub struct Subject<'a> {
+    pub amount: f64,
+    pub note: Option<&'a str>,
+    pub payee: Option<&'a RecordId>,
+}
+
+#[derive(Default)]
+pub struct Outcome {
+    pub category: Option<RecordId>,
+    pub tags: Vec<RecordId>,
+}
+
+pub fn compile(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+/// A user's rules, in the order they are evaluated, with their regexes
+/// compiled up front.
+pub struct RuleSet {
+    rules: Vec<(Rule, Option<Regex>)>,
+}
+
+impl RuleSet {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        let rules = rules
+            .into_iter()
+            .filter_map(
+                |rule| match rule.conditions.note_regex.as_deref().map(compile) {
+                    Some(Ok(regex)) => Some((rule, Some(regex))),
+                    // Patterns are validated on save, so this only skips rules
+                    // written by hand into the database.
+                    Some(Err(_)) => None,
+                    None => Some((rule, None)),
+                },
+            )
+            .collect();
+
+        Self { rules }
+    }
+
+    fn matches(rule: &Rule, regex: Option<&Regex>, subject: &Subject) -> bool {
+        let conditions = &rule.conditions;
+        let note = subject.note.unwrap_or_default();
+
+        if let Some(needle) = &conditions.note_contains
+            && !note.to_lowercase().contains(&needle.to_lowercase())
+        {
+            return false;
+        }
+
+        if let Some(regex) = regex
+            && !regex.is_match(note)
+        {
+            return false;
+        }
+
+        if conditions
+            .min_amount
+            .is_some_and(|min| subject.amount < min)
+            || conditions
+                .max_amount
+                .is_some_and(|max| subject.amount > max)
+        {
+            return false;
+        }
+
+        if conditions.payee.is_some() && conditions.payee.as_ref() != subject.payee {
+            return false;
+        }
+
+        true
+    }
+
+    /// The first matching rule with a category decides the category, while
+    /// the tags of every matching rule are combined.
+    pub fn evaluate(&self, subject: &Subject) -> Outcome {
+        let mut outcome = Outcome::default();
+
+        for (rule, regex) in &self.rules {
+            if !Self::matches(rule, regex.as_ref(), subject) {
+                continue;
+            }
+
+            if outcome.category.is_none() {
+                outcome.category = rule.category.clone();
+            }
+
+            for tag in &rule.tags {
+                if !outcome.tags.contains(tag) {
+                    outcome.tags.push(tag.clone());
+                }
+            }
+        }
+
+        outcome
+    }
+}
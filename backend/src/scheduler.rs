@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::{
+    config::config,
+    db::{
+        ApiDb, DbError,
+        repo::{AttachmentRepo, CategoryRepo, RecurringRepo, transaction_repo::TransactionRepo},
+    },
+    recurrence, storage,
+};
+
+/// How long an unreferenced attachment file is kept after it was last
+/// written, covering uploads that are still being stored.
+const PURGE_GRACE: Duration = Duration::from_secs(60 * 60);
+
+pub fn spawn(db: ApiDb) {
+    let period = Duration::from_secs(config().scheduler.interval);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(period);
+
+        loop {
+            ticker.tick().await;
+
+            if let Err(e) = materialize_recurring(&db).await {
+                tracing::error!("Failed to materialize recurring transactions: {e}");
+            }
+
+            if let Err(e) = purge_trash(&db).await {
+                tracing::error!("Failed to purge trash: {e}");
+            }
+
+            if let Err(e) = purge_attachments(&db).await {
+                tracing::error!("Failed to purge attachments: {e}");
+            }
+        }
+    });
+}
+
+async fn materialize_recurring(db: &ApiDb) -> Result<(), DbError> {
+    let recurring_repo = RecurringRepo::new(db);
+
+    let now = Utc::now();
+
+    for recurring in recurring_repo.list_due(now.into()).await? {
+        let mut n = recurring.occurrences;
+
+        while let Some(date) = recurrence::occurrence(&recurring.schedule, n)
+            && date <= now
+        {
+            recurring_repo.materialize(&recurring, date.into()).await?;
+
+            n += 1;
+        }
+
+        let next_date = recurrence::occurrence(&recurring.schedule, n).map(Into::into);
+
+        recurring_repo
+            .advance(recurring.id, recurring.occurrences, n, next_date)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn purge_trash(db: &ApiDb) -> Result<(), DbError> {
+    let before = Utc::now() - chrono::Duration::days(config().trash.retention_days);
+
+    TransactionRepo::new(db)
+        .purge_trashed(before.into())
+        .await?;
+    CategoryRepo::new(db).purge_trashed(before.into()).await?;
+
+    Ok(())
+}
+
+async fn purge_attachments(db: &ApiDb) -> Result<(), DbError> {
+    let repo = AttachmentRepo::new(db);
+
+    for hash in repo.list_purgeable().await? {
+        match storage::remove_stale(&hash, PURGE_GRACE).await {
+            Ok(true) => repo.clear_purge(hash).await?,
+            // Written again since; the next run checks whether it's in use.
+            Ok(false) => (),
+            Err(e) => tracing::error!("Failed to remove attachment {hash}: {e}"),
+        }
+    }
+
+    Ok(())
+}
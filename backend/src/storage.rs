@@ -0,0 +1,115 @@
+use std::{
+    io,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+use crate::config::config;
+
+/// Sniffs the MIME type of an upload from its leading bytes, returning `None`
+/// for anything that isn't an accepted receipt format.
+pub fn sniff_mime(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some("image/png"),
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some("image/gif"),
+        [
+            b'R',
+            b'I',
+            b'F',
+            b'F',
+            _,
+            _,
+            _,
+            _,
+            b'W',
+            b'E',
+            b'B',
+            b'P',
+            ..,
+        ] => Some("image/webp"),
+        [
+            _,
+            _,
+            _,
+            _,
+            b'f',
+            b't',
+            b'y',
+            b'p',
+            b'h',
+            b'e',
+            b'i',
+            b'c' | b'x',
+            ..,
+        ] => Some("image/heic"),
+        [b'%', b'P', b'D', b'F', b'-', ..] => Some("application/pdf"),
+        _ => None,
+    }
+}
+
+fn path(hash: &str) -> PathBuf {
+    PathBuf::from(&config().attachments.dir)
+        .join(&hash[..2])
+        .join(hash)
+}
+
+/// The SHA-256 digest `bytes` are stored under.
+pub fn digest(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// Stores `bytes` under `hash`, their digest. Identical uploads share a
+/// single file on disk; reusing one refreshes its modification time so that
+/// `remove_stale` leaves it alone.
+pub async fn write(hash: &str, bytes: &[u8]) -> io::Result<()> {
+    let path = path(hash);
+
+    if fs::try_exists(&path).await? {
+        let file = fs::File::options().append(true).open(&path).await?;
+        let file = file.into_std().await;
+
+        return tokio::task::spawn_blocking(move || file.set_modified(SystemTime::now()))
+            .await
+            .map_err(io::Error::other)?;
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes).await?;
+    fs::rename(&tmp, &path).await?;
+
+    Ok(())
+}
+
+pub async fn read(hash: &str) -> io::Result<Vec<u8>> {
+    fs::read(path(hash)).await
+}
+
+/// Removes the file stored under `hash` unless it was written within
+/// `grace`, in which case an upload may be about to reference it again and
+/// `false` is returned.
+pub async fn remove_stale(hash: &str, grace: Duration) -> io::Result<bool> {
+    let path = path(hash);
+
+    let modified = match fs::metadata(&path).await {
+        Ok(metadata) => metadata.modified()?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(true),
+        Err(e) => return Err(e),
+    };
+
+    if !modified.elapsed().is_ok_and(|age| age >= grace) {
+        return Ok(false);
+    }
+
+    match fs::remove_file(path).await {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(true),
+    }
+}